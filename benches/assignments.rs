@@ -0,0 +1,148 @@
+//! Benchmarks preparing the input, parsing it and solving both parts of every assignment, on its
+//! examples and on the real input when it is available. Preparing normalizes the input and runs the
+//! validator, if the assignment has one. The parts solve from an input that is parsed beforehand,
+//! so they leave out parsing.
+//!
+//! `cargo bench -- --save-baseline <name>` saves the results as a baseline, which
+//! `cargo bench -- --baseline <name>` compares against. The HTML report is written to
+//! `target/criterion/report/index.html`. With the `count-allocations` feature, the heap usage of
+//! every benchmark is written next to its results as `allocations.json`.
+
+use std::hint::black_box;
+use std::path::PathBuf;
+
+use aoc2023::allocation::{self, AllocationStats};
+use aoc2023::{get_assignments, Assignment, TestCase};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// An input of an assignment, named after the test case it belongs to.
+struct NamedInput {
+    name: &'static str,
+    /// The part whose solver is benchmarked on this input, or `None` for both parts.
+    part_number: Option<u8>,
+    input: String,
+}
+
+/// Returns the examples and the real input of the assignment, leaving out the second example if
+/// it is the same as the first one.
+fn _inputs(assignment: &Assignment) -> Vec<NamedInput> {
+    let example = |case: &Option<TestCase>| case.as_ref()?.input.map(str::to_string);
+    let example_1 = example(&assignment.cases.example_day_1);
+    let example_2 = example(&assignment.cases.example_day_2);
+    let real = std::fs::read_to_string(assignment.input_file_path()).ok();
+
+    let inputs = match example_1 == example_2 {
+        true => vec![("example", None, example_1), ("real", None, real)],
+        false => vec![
+            ("example_1", Some(1), example_1),
+            ("example_2", Some(2), example_2),
+            ("real", None, real),
+        ],
+    };
+
+    inputs
+        .into_iter()
+        .filter_map(|(name, part_number, input)| {
+            Some(NamedInput {
+                name,
+                part_number,
+                input: input?,
+            })
+        })
+        .collect()
+}
+
+/// Returns the directory Criterion writes its results to.
+fn _criterion_directory() -> PathBuf {
+    match std::env::var_os("CRITERION_HOME") {
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from(std::env::var_os("CARGO_TARGET_DIR").unwrap_or("target".into()))
+            .join("criterion"),
+    }
+}
+
+/// Records the heap usage of a single run next to the results of the benchmark, as Criterion only
+/// records runtimes. Records nothing unless the `count-allocations` feature is enabled.
+fn _record_allocations(id: [&str; 3], stats: Option<AllocationStats>) {
+    let Some(stats) = stats else {
+        return;
+    };
+    println!("{}: {}", id.join("/"), stats);
+
+    let directory = id
+        .iter()
+        .fold(_criterion_directory(), |path, part| path.join(part));
+    let path = directory.join("allocations.json");
+    let json = serde_json::to_string_pretty(&stats).unwrap();
+    if let Err(e) = std::fs::create_dir_all(&directory).and_then(|_| std::fs::write(&path, json)) {
+        panic!("Could not write {}: {}", path.display(), e);
+    }
+}
+
+fn bench_assignments(c: &mut Criterion) {
+    for assignment in get_assignments() {
+        let group_name = format!("day{:02}", assignment.day);
+        let mut group = c.benchmark_group(&group_name);
+
+        for NamedInput {
+            name,
+            part_number,
+            input,
+        } in _inputs(&assignment)
+        {
+            let id = BenchmarkId::new("prepare", name);
+            let mut is_reported = false;
+            group.bench_with_input(id, &input, |b, input| {
+                if !is_reported {
+                    let (_, stats) = allocation::measure(|| assignment.prepare(input));
+                    _record_allocations([&group_name, "prepare", name], stats);
+                    is_reported = true;
+                }
+                b.iter(|| assignment.prepare(black_box(input)))
+            });
+
+            // An input that is rejected has nothing left to parse or solve.
+            let Ok(prepared) = assignment.prepare(&input) else {
+                continue;
+            };
+
+            let id = BenchmarkId::new("parse", name);
+            let mut is_reported = false;
+            group.bench_with_input(id, &prepared, |b, input| {
+                if !is_reported {
+                    let (_, stats) = allocation::measure(|| assignment.parse(input));
+                    _record_allocations([&group_name, "parse", name], stats);
+                    is_reported = true;
+                }
+                b.iter(|| assignment.parse(black_box(input)))
+            });
+
+            let Ok(solver) = assignment.parse(&prepared) else {
+                continue;
+            };
+
+            for part in [1, 2]
+                .into_iter()
+                .filter(|p| part_number.unwrap_or(*p) == *p)
+            {
+                let function_name = format!("part_{}", part);
+                let id = BenchmarkId::new(&function_name, name);
+                let mut is_reported = false;
+                group.bench_function(id, |b| {
+                    if !is_reported {
+                        let (_, stats) =
+                            allocation::measure(|| assignment.solve_parsed(&solver, part));
+                        _record_allocations([&group_name, &function_name, name], stats);
+                        is_reported = true;
+                    }
+                    b.iter(|| assignment.solve_parsed(black_box(&solver), part))
+                });
+            }
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_assignments);
+criterion_main!(benches);
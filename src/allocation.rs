@@ -0,0 +1,143 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// The heap usage of a single measured run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AllocationStats {
+    /// The number of allocations, where every reallocation counts as a new allocation.
+    pub allocations: u64,
+    /// The total size of all allocations.
+    pub bytes: u64,
+    /// The most bytes that were allocated at once, on top of what was allocated before the run.
+    pub peak_bytes: u64,
+}
+
+impl Display for AllocationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} allocated, {} peak",
+            self.allocations,
+            format_bytes(self.bytes),
+            format_bytes(self.peak_bytes)
+        )
+    }
+}
+
+/// Formats a number of bytes in the largest binary unit (B, KiB, MiB or GiB) in which it is at
+/// least 1.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{}{}", bytes, UNITS[0]),
+        _ => format!("{:.1}{}", value, UNITS[unit]),
+    }
+}
+
+/// Runs the function and returns its heap usage next to its result, or `None` if allocations are
+/// not counted because the `count-allocations` feature is disabled.
+///
+/// The counters are shared by all threads, so allocations made by other threads in the meantime
+/// are included. Run one test case at a time for exact numbers.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocationStats>) {
+    #[cfg(feature = "count-allocations")]
+    {
+        let (result, stats) = counting::measure(f);
+        (result, Some(stats))
+    }
+
+    #[cfg(not(feature = "count-allocations"))]
+    {
+        (f(), None)
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::AllocationStats;
+
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static BYTES: AtomicU64 = AtomicU64::new(0);
+    static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+    static PEAK_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    /// Forwards to the system allocator while keeping count of what is allocated.
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn _record_allocation(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        let live_bytes = LIVE_BYTES.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+        PEAK_BYTES.fetch_max(live_bytes, Ordering::Relaxed);
+    }
+
+    fn _record_deallocation(size: usize) {
+        LIVE_BYTES.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc(layout);
+            if !pointer.is_null() {
+                _record_allocation(layout.size());
+            }
+            pointer
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc_zeroed(layout);
+            if !pointer.is_null() {
+                _record_allocation(layout.size());
+            }
+            pointer
+        }
+
+        unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+            System.dealloc(pointer, layout);
+            _record_deallocation(layout.size());
+        }
+
+        unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_pointer = System.realloc(pointer, layout, new_size);
+            if !new_pointer.is_null() {
+                _record_deallocation(layout.size());
+                _record_allocation(new_size);
+            }
+            new_pointer
+        }
+    }
+
+    pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocationStats) {
+        let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes_before = BYTES.load(Ordering::Relaxed);
+        let live_bytes_before = LIVE_BYTES.load(Ordering::Relaxed);
+        PEAK_BYTES.store(live_bytes_before, Ordering::Relaxed);
+
+        let result = f();
+
+        let stats = AllocationStats {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations_before,
+            bytes: BYTES.load(Ordering::Relaxed) - bytes_before,
+            peak_bytes: PEAK_BYTES
+                .load(Ordering::Relaxed)
+                .saturating_sub(live_bytes_before),
+        };
+
+        (result, stats)
+    }
+}
@@ -1,6 +1,5 @@
 use super::prelude::*;
-use regex::Regex;
-use std::ops::Range;
+use std::collections::VecDeque;
 
 pub fn get_assignment() -> Assignment {
     Assignment::new(
@@ -8,7 +7,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 1,
             description: "Calorie Counting",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 1abc2
@@ -29,102 +28,322 @@ zoneight234
             ),
             answer_example_day_2: Some(281.into()),
             answer_day_1: Some(54877.into()),
-            answer_day_2: None,
+            answer_day_2: Some(54100.into()),
+            normalization: InputNormalization::default(),
+            validate: None,
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    const WORD_TO_DIGIT: [(&str, u8); 9] = [
-        ("one", 1),
-        ("two", 2),
-        ("three", 3),
-        ("four", 4),
-        ("five", 5),
-        ("six", 6),
-        ("seven", 7),
-        ("eight", 8),
-        ("nine", 9),
-    ];
-
-    let res = context
-        .data
+const WORD_TO_DIGIT: [(&str, u8); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+/// Splits the input into lines, as the digits are only found while solving.
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let lines = input.lines().collect::<Vec<_>>();
+
+    Ok(Box::new(move |context| _run(&lines, context)))
+}
+
+fn _run(lines: &[&str], context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
+    // Part 1 only counts digits, part 2 also counts spelled-out digits. Words may overlap, so the
+    // last digit of "oneight" is 8 even though its "e" is shared with "one".
+    let matcher = DigitMatcher::new(context.part_number == 2);
+
+    let res = lines
         .iter()
         .map(|line| {
-            let mut all_numbers = vec![];
+            if context.logging_enabled {
+                println!("\"{line}\"");
+                println!(
+                    "digits: {:?}",
+                    matcher.find_digits(line).collect::<Vec<_>>()
+                );
+            }
 
-            let numbers_by_index = line
-                .chars()
-                .enumerate()
-                .filter_map(|(index, c)| c.to_digit(10).map(|d| (index, d as u8)))
-                .collect::<Vec<_>>();
-            all_numbers.extend(numbers_by_index.clone());
-
-            if context.part_number == 1 {
-                let regex_matches_per_digit = WORD_TO_DIGIT.map(|(word, digit)| {
-                    let pattern = Regex::new(word).unwrap();
-                    pattern
-                        .find_iter(line)
-                        .map(|m| (m.range(), digit))
-                        .collect::<Vec<_>>()
-                });
+            let mut digits = matcher.find_digits(line);
+            let Some(first) = digits.next() else {
+                return 0;
+            };
+            let last = digits.last().unwrap_or(first);
+
+            let res = (first.digit * 10 + last.digit) as u64;
+            if context.logging_enabled {
+                println!("res:    {}", res);
+                println!();
+            }
+
+            res
+        })
+        .sum::<u64>();
+
+    Ok(Some(res.into()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DigitMatch {
+    /// The byte index in the line at which the digit or its word starts.
+    start: usize,
+    digit: u8,
+}
 
-                let mut regex_matches_with_range = regex_matches_per_digit
-                    .iter()
-                    .flat_map(|e| e)
-                    .collect::<Vec<_>>();
+/// Finds digits, and optionally spelled-out digits, in a single pass over a line.
+///
+/// The words are matched by an Aho-Corasick automaton, so overlapping words such as the "two" and
+/// "one" in "twone" are both found.
+struct DigitMatcher {
+    /// The next state for every state and input byte, where state 0 is the start state.
+    transitions: Vec<[u8; 256]>,
+    /// The digit and length of the word that ends in each state, if any.
+    outputs: Vec<Option<(u8, usize)>>,
+}
 
-                regex_matches_with_range
-                    .sort_by(|(range_a, _), (range_b, _)| range_a.start.cmp(&range_b.start));
+impl DigitMatcher {
+    fn new(include_words: bool) -> Self {
+        let mut matcher = DigitMatcher {
+            transitions: vec![[0; 256]],
+            outputs: vec![None],
+        };
 
-                let regex_matches_by_index = regex_matches_with_range
-                    .iter()
-                    .fold(Vec::<&(Range<usize>, u8)>::new(), |mut acc, cur| {
-                        if !acc.iter().any(|e| e.0.end > cur.0.start) {
-                            acc.push(cur)
+        if include_words {
+            for (word, digit) in WORD_TO_DIGIT {
+                matcher._insert(word, digit);
+            }
+            matcher._link_failures();
+        }
+
+        matcher
+    }
+
+    /// Adds a word to the trie of states, in which 0 still means there is no transition.
+    fn _insert(&mut self, word: &str, digit: u8) {
+        let mut state = 0;
+        for byte in word.bytes() {
+            state = match self.transitions[state][byte as usize] {
+                0 => {
+                    self.transitions.push([0; 256]);
+                    self.outputs.push(None);
+                    let next_state = self.transitions.len() - 1;
+                    self.transitions[state][byte as usize] = next_state as u8;
+                    next_state
+                }
+                next_state => next_state as usize,
+            };
+        }
+        self.outputs[state] = Some((digit, word.len()));
+    }
+
+    /// Turns the trie into an automaton by pointing every missing transition to where the
+    /// longest suffix of the text matched so far would continue.
+    fn _link_failures(&mut self) {
+        let mut failures = vec![0; self.transitions.len()];
+        let mut queue = self.transitions[0]
+            .iter()
+            .filter(|next_state| **next_state != 0)
+            .map(|next_state| *next_state as usize)
+            .collect::<VecDeque<_>>();
+
+        while let Some(state) = queue.pop_front() {
+            for byte in 0..256 {
+                let failure_transition = self.transitions[failures[state]][byte];
+                match self.transitions[state][byte] as usize {
+                    0 => self.transitions[state][byte] = failure_transition,
+                    next_state => {
+                        failures[next_state] = failure_transition as usize;
+                        if self.outputs[next_state].is_none() {
+                            self.outputs[next_state] = self.outputs[failure_transition as usize];
                         }
-                        acc
-                    })
-                    .iter()
-                    .map(|(range, digit)| (range.start, *digit))
-                    .collect::<Vec<_>>();
-
-                all_numbers.extend(regex_matches_by_index.clone());
-
-                if context.logging_enabled {
-                    println!("\"{line}\"");
-                    println!("numbers_by_index:       {:?}", numbers_by_index);
-                    println!("regex_matches_per_digit: {:?}", regex_matches_per_digit);
-                    println!("regex_matches_by_index: {:?}", regex_matches_by_index);
+                        queue.push_back(next_state);
+                    }
                 }
             }
+        }
+    }
 
-            all_numbers.sort_by_key(|(index, _)| *index);
-            if context.logging_enabled {
-                println!("all_numbers:            {:?}", all_numbers);
+    /// Returns all digits in the line in order. No word contains another, so ordering them by
+    /// where they end also orders them by where they start.
+    fn find_digits<'a>(&'a self, line: &'a str) -> impl Iterator<Item = DigitMatch> + 'a {
+        let mut state = 0;
+
+        line.bytes().enumerate().filter_map(move |(index, byte)| {
+            state = self.transitions[state][byte as usize] as usize;
+
+            if byte.is_ascii_digit() {
+                return Some(DigitMatch {
+                    start: index,
+                    digit: byte - b'0',
+                });
             }
 
-            if all_numbers.is_empty() {
-                return 0;
+            self.outputs[state].map(|(digit, length)| DigitMatch {
+                start: index + 1 - length,
+                digit,
+            })
+        })
+    }
+}
+
+/// Generates `size` lines of letters, digits, spelled-out digits and their beginnings, each with at
+/// least one digit.
+fn _generate(random: &mut Random, size: usize) -> String {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+    let random_digit = |random: &mut Random| char::from(b'1' + random.range(0..9) as u8);
+
+    let lines = (0..size).map(|_| {
+        let mut line = String::new();
+        for _ in 0..random.range(1..8) {
+            match random.range(0..5) {
+                0 => line.push_str(random.pick(&WORD_TO_DIGIT).0),
+                1 => {
+                    let word = random.pick(&WORD_TO_DIGIT).0;
+                    line.push_str(&word[..random.range(1..word.len() as u64) as usize]);
+                }
+                2 => line.push(random_digit(random)),
+                _ => line.push(char::from(*random.pick(LETTERS))),
             }
+        }
+        let digit_index = random.range(0..line.len() as u64 + 1) as usize;
+        line.insert(digit_index, random_digit(random));
 
-            let res = format!(
-                "{}{}",
-                all_numbers.first().unwrap().1,
-                all_numbers.last().unwrap().1
-            )
-            .parse::<u64>()
-            .unwrap();
+        line
+    });
 
-            if context.logging_enabled {
-                println!("res:                    {}", res);
-                println!("");
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use test_case::test_case;
+
+    use super::super::testing;
+    use super::*;
+
+    /// Looks for a digit or a digit word at every position of every line.
+    fn _solve_by_brute_force(input: &str, part_number: u8) -> Option<Answer> {
+        let calibration_values = input.lines().map(|line| {
+            let digits = (0..line.len())
+                .filter_map(|index| {
+                    let rest = &line[index..];
+                    let digit = rest.chars().next()?.to_digit(10).map(|d| d as u64);
+                    let word_digit = WORD_TO_DIGIT
+                        .iter()
+                        .filter(|_| part_number == 2)
+                        .find(|(word, _)| rest.starts_with(word))
+                        .map(|(_, digit)| *digit as u64);
+                    digit.or(word_digit)
+                })
+                .collect::<Vec<_>>();
+
+            match (digits.first(), digits.last()) {
+                (Some(first), Some(last)) => first * 10 + last,
+                _ => 0,
             }
+        });
 
-            res
-        })
-        .sum::<u64>();
+        Some(calibration_values.sum::<u64>().into())
+    }
 
-    Ok(Some(res.into()))
+    #[test]
+    fn matches_brute_force_on_generated_documents() {
+        testing::check_against_reference(&get_assignment(), 10, _solve_by_brute_force);
+    }
+
+    /// Finds the digits with a regex per word, with the matches of all words sorted by where they
+    /// start. Unlike the regex scan this assignment used before the automaton, it keeps words that
+    /// overlap, such as both words of `oneight`. The old scan dropped the second one, which is
+    /// what the automaton fixed.
+    fn _find_digits_with_regexes(line: &str) -> Vec<DigitMatch> {
+        let mut digits = line
+            .char_indices()
+            .filter_map(|(start, c)| {
+                c.to_digit(10).map(|digit| DigitMatch {
+                    start,
+                    digit: digit as u8,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (word, digit) in WORD_TO_DIGIT {
+            let pattern = Regex::new(word).unwrap();
+            digits.extend(pattern.find_iter(line).map(|m| DigitMatch {
+                start: m.start(),
+                digit,
+            }));
+        }
+
+        digits.sort_by_key(|digit| digit.start);
+        digits
+    }
+
+    #[test_case("twone" => vec![2, 1])]
+    #[test_case("eightwo" => vec![8, 2])]
+    #[test_case("oneight" => vec![1, 8])]
+    #[test_case("sevenine2" => vec![7, 9, 2])]
+    #[test_case("onone" => vec![1]; "restarts halfway through a word")]
+    #[test_case("abc" => Vec::<u8>::new())]
+    fn finds_overlapping_words(line: &str) -> Vec<u8> {
+        DigitMatcher::new(true)
+            .find_digits(line)
+            .map(|digit| digit.digit)
+            .collect()
+    }
+
+    #[test_case(1, "oneight" => Some(Answer::Integer(0)); "part 1 ignores words")]
+    #[test_case(1, "a1b2c3" => Some(Answer::Integer(13)); "part 1 uses digits")]
+    #[test_case(2, "oneight" => Some(Answer::Integer(18)); "part 2 uses overlapping last word")]
+    #[test_case(2, "xtwone3four" => Some(Answer::Integer(24)); "part 2 mixes words and digits")]
+    #[test_case(2, "4" => Some(Answer::Integer(44)); "single digit is first and last")]
+    fn calibrates_line(part_number: u8, line: &str) -> Option<Answer> {
+        _run(
+            &[line],
+            AssignmentRuntimeContext {
+                part_number,
+                is_example: true,
+                logging_enabled: false,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ignores_words_when_disabled() {
+        let digits = DigitMatcher::new(false)
+            .find_digits("one2three")
+            .collect::<Vec<_>>();
+
+        assert_eq!(digits, vec![DigitMatch { start: 3, digit: 2 }]);
+    }
+
+    #[test]
+    fn matches_regexes_on_random_lines() {
+        let alphabet = "onetwhrfuivsxg19".as_bytes();
+        let matcher = DigitMatcher::new(true);
+        let mut random = Random::new(1);
+
+        for _ in 0..5_000 {
+            let length = random.range(0..24);
+            let line = (0..length)
+                .map(|_| alphabet[random.range(0..alphabet.len() as u64) as usize] as char)
+                .collect::<String>();
+
+            assert_eq!(
+                matcher.find_digits(&line).collect::<Vec<_>>(),
+                _find_digits_with_regexes(&line),
+                "{}",
+                line
+            );
+        }
+    }
 }
@@ -1,5 +1,8 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
 use super::prelude::*;
-use std::collections::HashMap;
 
 pub fn get_assignment() -> Assignment {
     Assignment::new(
@@ -7,7 +10,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 2,
             description: "Cube Conundrum",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
@@ -28,64 +31,45 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
             ),
             answer_example_day_2: Some(2286.into()),
             answer_day_2: Some(65371.into()),
+            normalization: InputNormalization::default(),
+            validate: Some(|input| Game::validate(input, &BAG)),
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    let games = context
-        .data
-        .iter()
-        .map(|line| Game::parse(&line))
-        .collect::<Vec<_>>();
+/// The cubes in the bag for part 1, which are also the only colors the input may use.
+const BAG: [(&str, u32); 3] = [("red", 12), ("green", 13), ("blue", 14)];
 
-    if context.part_number == 1 {
-        _run_part_1(context, games)
-    } else {
-        _run_part_2(context, games)
-    }
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let bag = CubeSet::from(&BAG[..]);
+    let games = Game::parse_all(input, &bag)?;
+
+    Ok(Box::new(move |context| {
+        if context.part_number == 1 {
+            _run_part_1(context, &games, &bag)
+        } else {
+            _run_part_2(context, &games)
+        }
+    }))
 }
 
 fn _run_part_1(
     context: AssignmentRuntimeContext,
-    games: Vec<Game>,
+    games: &[Game],
+    bag: &CubeSet,
 ) -> Result<Option<Answer>, String> {
-    let limits = CubeLimits {
-        red: 12,
-        green: 13,
-        blue: 14,
-    };
-
     if context.logging_enabled {
-        for game in &games {
-            print!("Game {}: ", game.id);
-            for pull in &game.pulls {
-                let mut pull_parts: Vec<String> = vec![];
-
-                if pull.reds != 0 {
-                    pull_parts.push(format!("{} red", pull.reds));
-                }
-                if pull.greens != 0 {
-                    pull_parts.push(format!("{} green", pull.greens));
-                }
-                if pull.blues != 0 {
-                    pull_parts.push(format!("{} blue", pull.blues));
-                }
-                print!("{}", pull_parts.join(", "));
-                print!("; ");
-            }
-            println!("");
+        for game in games {
+            let pulls = game.pulls.iter().map(|pull| pull.to_string()).join("; ");
+            println!("Game {}: {}", game.id, pulls);
         }
     }
 
     let possible_game_ids_sum = games
         .iter()
-        .filter(|game| {
-            game.pulls.iter().all(|pull| {
-                pull.reds <= limits.red && pull.greens <= limits.green && pull.blues <= limits.blue
-            })
-        })
+        .filter(|game| game.is_possible_with(bag))
         .map(|game| game.id)
         .sum::<u32>();
 
@@ -93,90 +77,314 @@ fn _run_part_1(
 }
 
 fn _run_part_2(
-    _context: AssignmentRuntimeContext,
-    games: Vec<Game>,
+    context: AssignmentRuntimeContext,
+    games: &[Game],
 ) -> Result<Option<Answer>, String> {
-    let games_with_minimal_pulls = games.iter().map(|game| {
-        (
-            game,
-            game.pulls.iter().fold(Pull::default(), |acc, pull| Pull {
-                reds: acc.reds.max(pull.reds),
-                greens: acc.greens.max(pull.greens),
-                blues: acc.blues.max(pull.blues),
-            }),
-        )
-    });
+    let colors = Game::colors(games);
 
-    let game_powers_summed = games_with_minimal_pulls
-        .map(|(_, pull)| pull.reds * pull.greens * pull.blues)
-        .sum::<u32>();
+    let game_powers_summed = games
+        .iter()
+        .map(|game| {
+            let minimal_bag = game.minimal_bag(&colors);
+            if context.logging_enabled {
+                println!(
+                    "Game {}: {} (power {})",
+                    game.id,
+                    minimal_bag,
+                    minimal_bag.power()
+                );
+            }
+            minimal_bag.power()
+        })
+        .sum::<u64>();
 
     Ok(Some(game_powers_summed.into()))
 }
 
-struct CubeLimits {
-    red: u32,
-    green: u32,
-    blue: u32,
-}
-
 #[derive(Debug)]
-struct Game {
+struct Game<'a> {
     id: u32,
-    pulls: Vec<Pull>,
+    pulls: Vec<CubeSet<'a>>,
 }
 
-impl Game {
-    fn parse(line: &String) -> Game {
-        let parts = line.split(":").collect::<Vec<_>>();
-        let game_string_part = parts.first().unwrap();
-        let pulls_string_part = parts.last().unwrap();
-
-        let id = game_string_part
-            .split(" ")
-            .last()
-            .unwrap()
-            .to_string()
-            .parse::<u32>()
-            .unwrap();
-
-        let pulls = pulls_string_part
-            .split(";")
-            .map(|pull_string| Pull::parse(&pull_string.to_owned()))
-            .collect::<Vec<_>>();
+impl<'a> Game<'a> {
+    /// Checks that the input parses and only uses the colors of the given limits.
+    fn validate(input: &str, limits: &[(&str, u32)]) -> Result<(), InputError> {
+        Game::parse_all(input, &CubeSet::from(limits))
+            .map(|_| ())
+            .map_err(InputError::from)
+    }
+
+    /// Parses all games, rejecting any color that the limits have no count for.
+    fn parse_all(input: &'a str, limits: &CubeSet) -> Result<Vec<Game<'a>>, ParseError> {
+        Parser::new(input)
+            .lines()
+            .map(|line| Game::parse(line, limits))
+            .collect()
+    }
+
+    fn parse(line: Parser<'a>, limits: &CubeSet) -> Result<Game<'a>, ParseError> {
+        let (mut header, pulls) = line.split_once(':')?;
+        header.literal("Game")?;
+        let id = header.integer::<u32>()?;
+        header.end()?;
+
+        let pulls = pulls
+            .separated(";")
+            .map(|pull| CubeSet::parse(pull, limits))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Game { id, pulls })
+    }
+
+    /// Returns every color that appears in the games, in the order of their first appearance.
+    fn colors(games: &[Game<'a>]) -> Vec<&'a str> {
+        games
+            .iter()
+            .flat_map(|game| &game.pulls)
+            .flat_map(|pull| pull.colors())
+            .unique()
+            .collect()
+    }
+
+    fn is_possible_with(&self, bag: &CubeSet) -> bool {
+        self.pulls.iter().all(|pull| bag.contains(pull))
+    }
+
+    /// Returns the fewest cubes of each of the given colors that make this game possible.
+    fn minimal_bag(&self, colors: &[&'a str]) -> CubeSet<'a> {
+        let mut minimal_bag = CubeSet::default();
+        for color in colors {
+            let count = self.pulls.iter().map(|pull| pull.get(color)).max();
+            minimal_bag.add(color, count.unwrap_or_default());
+        }
 
-        Game { id, pulls: pulls }
+        minimal_bag
     }
 }
 
+/// A number of cubes per color, in the order the colors first appeared.
 #[derive(Debug, Default)]
-struct Pull {
-    reds: u32,
-    greens: u32,
-    blues: u32,
+struct CubeSet<'a> {
+    counts: Vec<(&'a str, u32)>,
 }
 
-impl Pull {
-    fn parse(line: &String) -> Pull {
-        let parts = line
-            .split(",")
-            .map(str::trim)
-            .map(|item| {
-                let item_parts = item.split(" ").collect::<Vec<_>>();
-                let count = item_parts.first().unwrap().parse::<u32>().unwrap();
-                let cube_type = item_parts.last().unwrap();
-
-                (*cube_type, count)
-            })
-            .fold(HashMap::<&str, u32>::new(), |mut acc, cur| {
-                acc.insert(cur.0, cur.1);
-                acc
-            });
-
-        Pull {
-            reds: *parts.get("red").unwrap_or(&0),
-            greens: *parts.get("green").unwrap_or(&0),
-            blues: *parts.get("blue").unwrap_or(&0),
+impl<'a> CubeSet<'a> {
+    fn parse(pull: Parser<'a>, limits: &CubeSet) -> Result<CubeSet<'a>, ParseError> {
+        let mut result = CubeSet::default();
+
+        for mut item in pull.separated(",") {
+            let count = item.integer::<u32>()?;
+            item.skip_whitespace();
+            let color_start = item;
+            let color = item.token()?;
+            item.end()?;
+            if !limits.colors().contains(&color) {
+                return Err(color_start.error(format!(
+                    "Unknown color {}, expected one of {}",
+                    color,
+                    limits.colors().join(", ")
+                )));
+            }
+
+            result.add(color, count);
+        }
+
+        Ok(result)
+    }
+
+    fn colors(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.counts.iter().map(|(color, _)| *color)
+    }
+
+    fn get(&self, color: &str) -> u32 {
+        self.counts
+            .iter()
+            .filter(|(c, _)| *c == color)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    fn add(&mut self, color: &'a str, count: u32) {
+        match self.counts.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, existing_count)) => *existing_count += count,
+            None => self.counts.push((color, count)),
+        }
+    }
+
+    /// Whether every color has at least as many cubes as in the other set.
+    fn contains(&self, other: &CubeSet) -> bool {
+        other
+            .counts
+            .iter()
+            .all(|(color, count)| self.get(color) >= *count)
+    }
+
+    /// The product of the counts of all colors.
+    fn power(&self) -> u64 {
+        self.counts.iter().map(|(_, count)| *count as u64).product()
+    }
+}
+
+impl<'a> From<&[(&'a str, u32)]> for CubeSet<'a> {
+    fn from(counts: &[(&'a str, u32)]) -> Self {
+        let mut cube_set = CubeSet::default();
+        for (color, count) in counts {
+            cube_set.add(color, *count);
         }
+
+        cube_set
+    }
+}
+
+impl Display for CubeSet<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = self
+            .counts
+            .iter()
+            .map(|(color, count)| format!("{} {}", count, color));
+        write!(f, "{}", counts.format(", "))
+    }
+}
+
+/// Generates `size` games of up to 6 pulls, which each show up to 20 cubes of some of the colors
+/// in the bag.
+fn _generate(random: &mut Random, size: usize) -> String {
+    let games = (1..=size).map(|id| {
+        let pulls = (0..random.range(1..7)).map(|_| {
+            let mut colors = BAG.map(|(color, _)| color);
+            random.shuffle(&mut colors);
+            let color_count = random.range(1..4) as usize;
+            colors[..color_count]
+                .iter()
+                .map(|color| format!("{} {}", random.range(1..21), color))
+                .join(", ")
+        });
+        format!("Game {}: {}", id, pulls.format("; "))
+    });
+
+    games.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::super::testing;
+    use super::*;
+
+    /// Checks every game against the bag, or multiplies the most cubes of each color it shows.
+    fn _solve_by_brute_force(input: &str, part_number: u8) -> Option<Answer> {
+        let games = input
+            .lines()
+            .map(|line| {
+                let (name, pulls) = line.split_once(": ").unwrap();
+                let id = name.trim_start_matches("Game ").parse::<u64>().unwrap();
+                let cubes = pulls
+                    .split([';', ','])
+                    .map(|cubes| {
+                        let (count, color) = cubes.trim().split_once(' ').unwrap();
+                        (color, count.parse::<u64>().unwrap())
+                    })
+                    .collect::<Vec<_>>();
+                (id, cubes)
+            })
+            .collect::<Vec<_>>();
+        let most_cubes = |cubes: &[(&str, u64)], color: &str| {
+            cubes
+                .iter()
+                .filter(|(c, _)| *c == color)
+                .map(|(_, count)| *count)
+                .max()
+                .unwrap_or(0)
+        };
+
+        let colors = games
+            .iter()
+            .flat_map(|(_, cubes)| cubes.iter().map(|(color, _)| *color));
+        let colors = colors.unique().collect::<Vec<_>>();
+
+        let answer = games
+            .iter()
+            .map(|(id, cubes)| match part_number {
+                1 => {
+                    let is_possible = BAG
+                        .iter()
+                        .all(|(color, limit)| most_cubes(cubes, color) <= *limit as u64);
+                    if is_possible {
+                        *id
+                    } else {
+                        0
+                    }
+                }
+                _ => colors
+                    .iter()
+                    .map(|color| most_cubes(cubes, color))
+                    .product(),
+            })
+            .sum::<u64>();
+
+        Some(answer.into())
+    }
+
+    #[test]
+    fn matches_brute_force_on_generated_games() {
+        testing::check_against_reference(&get_assignment(), 10, _solve_by_brute_force);
+    }
+
+    #[test]
+    fn finds_minimal_bag_and_power() {
+        let game = Game::parse(
+            Parser::new("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"),
+            &CubeSet::from(&BAG[..]),
+        )
+        .unwrap();
+
+        let minimal_bag = game.minimal_bag(&["red", "green", "blue"]);
+
+        assert_eq!(minimal_bag.to_string(), "4 red, 2 green, 6 blue");
+        assert_eq!(minimal_bag.power(), 48);
+    }
+
+    #[test]
+    fn supports_other_colors() {
+        let limits = CubeSet::from(&[("purple", 0), ("red", 0), ("cyan", 0)][..]);
+        let games =
+            Game::parse_all("Game 7: 3 purple; 2 red, 5 purple\nGame 8: 1 cyan", &limits).unwrap();
+        let colors = Game::colors(&games);
+
+        assert_eq!(colors, ["purple", "red", "cyan"]);
+        assert!(games[0].is_possible_with(&CubeSet::from(&[("red", 2), ("purple", 5)][..])));
+        assert!(!games[0].is_possible_with(&CubeSet::from(&[("red", 2), ("purple", 4)][..])));
+        assert_eq!(games[0].minimal_bag(&colors[..2]).power(), 10);
+        assert_eq!(games[1].minimal_bag(&colors).power(), 0);
+    }
+
+    #[test_case("Game 1: 3 blue\nGame 2: 1 red" => Some(Answer::Integer(3)); "all games")]
+    #[test_case("Game 1: 3 blue\nGame 2: 13 red" => Some(Answer::Integer(1)); "too many red")]
+    fn sums_possible_games(input: &str) -> Option<Answer> {
+        _parse(input).unwrap()(AssignmentRuntimeContext {
+            part_number: 1,
+            is_example: true,
+            logging_enabled: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_the_line_and_column_of_errors() {
+        let error = Game::validate("Game 1: 3 blue\nGame 2: 1 red, x purple", &BAG).unwrap_err();
+
+        assert_eq!(error.to_string(), "line 2, column 16: Expected an integer");
+    }
+
+    #[test]
+    fn rejects_colors_without_limits() {
+        let error = Game::validate("Game 1: 3 blue\nGame 2: 1 red, 2 pink", &BAG).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 18: Unknown color pink, expected one of red, green, blue"
+        );
     }
 }
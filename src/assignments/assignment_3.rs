@@ -1,8 +1,11 @@
-use std::ops::RangeInclusive;
+use std::collections::HashSet;
 
 use itertools::Itertools;
+use owo_colors::OwoColorize;
 
+use super::grid::{Grid, Point, Run};
 use super::prelude::*;
+use super::ByteGrid;
 
 pub fn get_assignment() -> Assignment {
     Assignment::new(
@@ -10,7 +13,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 3,
             description: "Gear Ratios",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 467..114..
@@ -41,198 +44,374 @@ pub fn get_assignment() -> Assignment {
             ),
             answer_example_day_2: Some(467835.into()),
             answer_day_2: Some(80179647.into()),
+            normalization: InputNormalization::default(),
+            validate: Some(_validate),
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    let matrix = EngineMatrix::parse(context.data);
+/// The schematic has to be a grid, with all rows of the same length.
+fn _validate(input: &str) -> Result<(), InputError> {
+    ByteGrid::new(input).map(|_| ())
+}
+
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let grid = ByteGrid::new(input).map_err(|e| e.to_string())?;
+    let matrix = EngineMatrix::parse(Grid::from_bytes(&grid, char::from));
+
+    Ok(Box::new(move |context| _run(&matrix, context)))
+}
+
+fn _run(
+    matrix: &EngineMatrix,
+    context: AssignmentRuntimeContext,
+) -> Result<Option<Answer>, String> {
+    if context.logging_enabled {
+        println!("{}", matrix.render());
+        for (id, symbol) in matrix.symbols.iter().enumerate() {
+            if let Some(ratio) = matrix.get_gear_ratio(id) {
+                let [a, b] = matrix.get_adjacent_numbers(id)[..] else {
+                    unreachable!("A gear always touches exactly two numbers");
+                };
+                println!(
+                    "Gear at ({}, {}): {} * {} = {}",
+                    symbol.position.x,
+                    symbol.position.y,
+                    matrix.numbers[a].value.bright_yellow(),
+                    matrix.numbers[b].value.bright_yellow(),
+                    ratio
+                );
+            }
+        }
+    }
 
     if context.part_number == 1 {
-        let numbers_surrounded_by_symbols = matrix
-            .symbols
+        let part_numbers = matrix
+            .numbers
             .iter()
-            .flat_map(|SymbolEntry { x, y, character: _ }| {
-                let x = *x as i64;
-                let y = *y as i64;
-                let surrounding_coords = vec![
-                    (x - 1, y - 1),
-                    (x, y - 1),
-                    (x + 1, y - 1), //
-                    (x - 1, y),
-                    (x, y),
-                    (x + 1, y), //
-                    (x - 1, y + 1),
-                    (x, y + 1),
-                    (x + 1, y + 1), //
-                ]
-                .into_iter()
-                .filter_map(|(x, y)| {
-                    if x < 0i64 || y < 0i64 {
-                        None
-                    } else {
-                        Some((x as usize, y as usize))
-                    }
-                })
-                .collect::<Vec<_>>();
-
-                surrounding_coords.into_iter().flat_map(|(x, y)| {
-                    matrix
-                        .numbers
-                        .iter()
-                        .filter(|e| e.y == y && e.x.contains(&x))
-                        .collect::<Vec<_>>()
-                })
-            })
-            .unique()
-            .map(|e| e.value);
+            .enumerate()
+            .filter(|(id, _)| !matrix.get_adjacent_symbols(*id).is_empty())
+            .map(|(_, number)| number.value);
 
-        let sum = numbers_surrounded_by_symbols.sum::<u32>();
+        let sum = part_numbers.sum::<u32>();
 
         Ok(Some(sum.into()))
     } else {
-        let numbers_surrounded_by_stars_multiplied =
-            matrix.symbols.iter().filter(|e| e.character == '*').map(
-                |SymbolEntry { x, y, character: _ }| {
-                    let x = *x as i64;
-                    let y = *y as i64;
-                    let surrounding_coords = vec![
-                        (x - 1, y - 1),
-                        (x, y - 1),
-                        (x + 1, y - 1), //
-                        (x - 1, y),
-                        (x, y),
-                        (x + 1, y), //
-                        (x - 1, y + 1),
-                        (x, y + 1),
-                        (x + 1, y + 1), //
-                    ]
-                    .into_iter()
-                    .filter_map(|(x, y)| {
-                        if x < 0i64 || y < 0i64 {
-                            None
-                        } else {
-                            Some((x as usize, y as usize))
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                    let numbers_surrounded = surrounding_coords
-                        .into_iter()
-                        .flat_map(|(x, y)| {
-                            matrix
-                                .numbers
-                                .iter()
-                                .filter(|e| e.y == y && e.x.contains(&x))
-                                .collect::<Vec<_>>()
-                        })
-                        .unique()
-                        .collect::<Vec<_>>();
-
-                    if numbers_surrounded.len() != 2 {
-                        0
-                    } else {
-                        vec![numbers_surrounded.first(), numbers_surrounded.last()]
-                            .iter()
-                            .map(|e| e.unwrap().value)
-                            .reduce(|a, b| a * b)
-                            .unwrap()
-                    }
-                },
-            );
+        let gear_ratios = matrix
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(id, _)| matrix.get_gear_ratio(id));
 
-        let sum = numbers_surrounded_by_stars_multiplied.sum::<u32>();
+        let sum = gear_ratios.sum::<u32>();
 
         Ok(Some(sum.into()))
     }
 }
 
+/// What occupies a cell of the schematic, by index into [`EngineMatrix::numbers`] or
+/// [`EngineMatrix::symbols`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Number(usize),
+    Symbol(usize),
+}
+
 struct EngineMatrix {
     numbers: Vec<NumberEntry>,
     symbols: Vec<SymbolEntry>,
-}
-
-#[derive(Debug)]
-enum Entry {
-    Number(NumberEntry),
-    Symbol(SymbolEntry),
+    /// Maps every cell to the number or symbol on it, so adjacency lookups don't have to search.
+    cells: Grid<Cell>,
+    /// The schematic as it was parsed, for rendering.
+    characters: Grid<char>,
 }
 
 impl EngineMatrix {
-    fn parse(lines: &Vec<String>) -> Self {
-        let entries = lines.iter().enumerate().flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .filter(|(_, chr)| chr.to_string() != ".")
-                .fold(Vec::<Entry>::new(), |mut acc, (x, chr)| {
-                    let previous_x = if x == 0 { 0 } else { x - 1 };
-                    let last_x = if acc.is_empty() { 0 } else { acc.len() - 1 };
-
-                    if let Some(next_number) = chr.to_digit(10) {
-                        let (delete_last, next_entry) = match acc.last() {
-                            Some(Entry::Number(last_number_entry))
-                                if last_number_entry.x.end() == &previous_x =>
-                            {
-                                (
-                                    true,
-                                    NumberEntry {
-                                        value: (last_number_entry.value * 10) + next_number,
-                                        x: *last_number_entry.x.start()..=x,
-                                        y,
-                                    },
-                                )
-                            }
-                            _ => (
-                                false,
-                                NumberEntry {
-                                    value: next_number,
-                                    x: x..=x,
-                                    y,
-                                },
-                            ),
-                        };
-
-                        if delete_last {
-                            acc.remove(last_x);
-                        }
-                        acc.push(Entry::Number(next_entry));
-                        acc
-                    } else {
-                        acc.push(Entry::Symbol(SymbolEntry {
-                            x,
-                            y,
-                            character: chr,
-                        }));
-                        acc
-                    }
-                })
-        });
+    fn parse(grid: Grid<char>) -> Self {
+        let numbers = grid
+            .runs(char::is_ascii_digit)
+            .into_iter()
+            .map(|run| {
+                let value = run
+                    .points()
+                    .filter_map(|point| grid.get(point)?.to_digit(10))
+                    .fold(0, |acc, digit| acc * 10 + digit);
+
+                NumberEntry { value, run }
+            })
+            .collect::<Vec<_>>();
 
-        let mut numbers = Vec::<NumberEntry>::new();
-        let mut symbols = Vec::<SymbolEntry>::new();
+        let symbols = grid
+            .iter()
+            .filter(|(_, chr)| **chr != '.' && !chr.is_ascii_digit())
+            .map(|(position, chr)| SymbolEntry {
+                character: *chr,
+                position,
+            })
+            .collect::<Vec<_>>();
 
-        for entry in entries {
-            match entry {
-                Entry::Number(e) => numbers.push(e),
-                Entry::Symbol(e) => symbols.push(e),
+        let mut cells = Grid::new(
+            grid.width(),
+            grid.height(),
+            vec![Cell::Empty; grid.width() * grid.height()],
+        );
+        for (id, number) in numbers.iter().enumerate() {
+            for point in number.run.points() {
+                if let Some(cell) = cells.get_mut(point) {
+                    *cell = Cell::Number(id);
+                }
             }
         }
+        for (id, symbol) in symbols.iter().enumerate() {
+            if let Some(cell) = cells.get_mut(symbol.position) {
+                *cell = Cell::Symbol(id);
+            }
+        }
+
+        EngineMatrix {
+            numbers,
+            symbols,
+            cells,
+            characters: grid,
+        }
+    }
+
+    /// Returns the ids of all numbers of which at least one digit touches the given symbol.
+    fn get_adjacent_numbers(&self, symbol_id: usize) -> Vec<usize> {
+        let position = self.symbols[symbol_id].position;
+
+        self.cells
+            .neighbors_8(position)
+            .filter_map(|neighbor| match self.cells.get(neighbor) {
+                Some(Cell::Number(id)) => Some(*id),
+                _ => None,
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns the ids of all symbols that touch at least one digit of the given number.
+    fn get_adjacent_symbols(&self, number_id: usize) -> Vec<usize> {
+        self.numbers[number_id]
+            .run
+            .points()
+            .flat_map(|point| self.cells.neighbors_8(point))
+            .filter_map(|neighbor| match self.cells.get(neighbor) {
+                Some(Cell::Symbol(id)) => Some(*id),
+                _ => None,
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns the product of the two numbers touching the given symbol, or `None` if it is not a
+    /// gear: a `*` that touches exactly two numbers.
+    fn get_gear_ratio(&self, symbol_id: usize) -> Option<u32> {
+        if self.symbols[symbol_id].character != '*' {
+            return None;
+        }
+
+        match self.get_adjacent_numbers(symbol_id)[..] {
+            [a, b] => Some(self.numbers[a].value * self.numbers[b].value),
+            _ => None,
+        }
+    }
+
+    /// Renders the schematic with part numbers in green, other numbers in red, and gears and
+    /// the numbers that make up their ratios in yellow.
+    fn render(&self) -> String {
+        let gear_ids = (0..self.symbols.len())
+            .filter(|id| self.get_gear_ratio(*id).is_some())
+            .collect::<HashSet<_>>();
+        let gear_number_ids = gear_ids
+            .iter()
+            .flat_map(|id| self.get_adjacent_numbers(*id))
+            .collect::<HashSet<_>>();
+
+        self.cells.render(|point, cell| match *cell {
+            Cell::Empty => ".".black().to_string(),
+            Cell::Symbol(id) if gear_ids.contains(&id) => self.symbols[id]
+                .character
+                .bright_yellow()
+                .bold()
+                .to_string(),
+            Cell::Symbol(id) => self.symbols[id].character.bold().to_string(),
+            Cell::Number(id) => {
+                let digit = self.characters.get(point).copied().unwrap_or('?');
 
-        EngineMatrix { numbers, symbols }
+                if gear_number_ids.contains(&id) {
+                    digit.bright_yellow().to_string()
+                } else if self.get_adjacent_symbols(id).is_empty() {
+                    digit.red().to_string()
+                } else {
+                    digit.green().to_string()
+                }
+            }
+        })
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 struct NumberEntry {
     value: u32,
-    x: RangeInclusive<usize>,
-    y: usize,
+    run: Run,
 }
 
 #[derive(Debug)]
 struct SymbolEntry {
     character: char,
-    x: usize,
-    y: usize,
+    position: Point,
+}
+
+/// Generates a schematic of `size` rows of 140 cells, with numbers of up to 3 digits and symbols.
+fn _generate(random: &mut Random, size: usize) -> String {
+    const WIDTH: usize = 140;
+    const SYMBOLS: &[u8] = b"*#+$/@=-%&";
+
+    let rows = (0..size).map(|_| {
+        let mut row = String::with_capacity(WIDTH + 4);
+        while row.len() < WIDTH {
+            match random.range(0..10) {
+                0..=1 => {
+                    row.push_str(&random.range(1..1000).to_string());
+                    row.push('.');
+                }
+                2 => row.push(char::from(*random.pick(SYMBOLS))),
+                _ => row.push('.'),
+            }
+        }
+        row.truncate(WIDTH);
+
+        row
+    });
+
+    rows.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::super::testing;
+    use super::*;
+
+    /// Finds the numbers row by row and checks the cells around each of them.
+    fn _solve_by_brute_force(input: &str, part_number: u8) -> Option<Answer> {
+        let rows = input.lines().map(str::as_bytes).collect::<Vec<_>>();
+        let mut numbers = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let length = row[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+                if length > 0 {
+                    let value = String::from_utf8_lossy(&row[x..x + length]).parse::<u64>();
+                    numbers.push((y, x..x + length, value.unwrap()));
+                }
+                x += length.max(1);
+            }
+        }
+
+        let is_adjacent = |(x, y): (usize, usize), (number_y, number_x, _): &(_, Range<_>, _)| {
+            y.abs_diff(*number_y) <= 1 && x + 1 >= number_x.start && x <= number_x.end
+        };
+        let cells = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, c)| ((x, y), *c)));
+
+        let answer = match part_number {
+            1 => numbers
+                .iter()
+                .filter(|number| {
+                    cells.clone().any(|(position, c)| {
+                        c != b'.' && !c.is_ascii_digit() && is_adjacent(position, number)
+                    })
+                })
+                .map(|(_, _, value)| value)
+                .sum::<u64>(),
+            _ => cells
+                .filter(|(_, c)| *c == b'*')
+                .map(|(position, _)| {
+                    let adjacent_numbers = numbers
+                        .iter()
+                        .filter(|number| is_adjacent(position, number))
+                        .collect::<Vec<_>>();
+                    match adjacent_numbers[..] {
+                        [(_, _, a), (_, _, b)] => a * b,
+                        _ => 0,
+                    }
+                })
+                .sum::<u64>(),
+        };
+
+        Some(answer.into())
+    }
+
+    /// Replaces every cell of a rendered schematic by the color it is shown in: `g` for green, `r`
+    /// for red, `y` for yellow, `.` for black and `-` for no color.
+    fn _colors(rendered: &str) -> String {
+        let mut colors = String::new();
+        let mut color = '-';
+        let mut characters = rendered.chars();
+        while let Some(character) = characters.next() {
+            match character {
+                '\x1b' => {
+                    let code = characters
+                        .by_ref()
+                        .take_while(|c| *c != 'm')
+                        .collect::<String>();
+                    color = match code.as_str() {
+                        "[30" => '.',
+                        "[31" => 'r',
+                        "[32" => 'g',
+                        "[93" => 'y',
+                        _ => color,
+                    };
+                }
+                '\n' => colors.push('\n'),
+                _ => {
+                    colors.push(color);
+                    color = '-';
+                }
+            }
+        }
+
+        colors
+    }
+
+    #[test]
+    fn colors_part_numbers_and_gears() {
+        let grid = ByteGrid::new("1#2...\n......\n3*4..5").unwrap();
+        let matrix = EngineMatrix::parse(Grid::from_bytes(&grid, char::from));
+
+        // Only a `*` is a gear, even though the `#` touches two numbers as well.
+        assert_eq!(_colors(&matrix.render()), "g-g...\n......\nyyy..r");
+        assert_eq!(matrix.get_gear_ratio(0), None);
+        assert_eq!(matrix.get_gear_ratio(1), Some(12));
+    }
+
+    #[test]
+    fn renders_the_digits_of_the_schematic() {
+        let grid = ByteGrid::new("007*..\n....12").unwrap();
+        let matrix = EngineMatrix::parse(Grid::from_bytes(&grid, char::from));
+
+        // Removes the color codes, which all end with an `m`.
+        let rendered = matrix
+            .render()
+            .split('\x1b')
+            .fold(String::new(), |text, part| match part.split_once('m') {
+                Some((_, rest)) if part.starts_with('[') => text + rest,
+                _ => text + part,
+            });
+
+        assert_eq!(rendered, "007*..\n....12");
+    }
+
+    #[test]
+    fn matches_brute_force_on_generated_schematics() {
+        testing::check_against_reference(&get_assignment(), 5, _solve_by_brute_force);
+    }
 }
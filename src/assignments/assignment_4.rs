@@ -1,5 +1,3 @@
-use std::collections::{HashMap, HashSet};
-
 use itertools::Itertools;
 
 use super::prelude::*;
@@ -10,7 +8,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 4,
             description: "Scratchcards",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -33,126 +31,254 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
             ),
             answer_example_day_2: Some(30.into()),
             answer_day_2: Some(8172507.into()),
+            normalization: InputNormalization::default(),
+            validate: Some(Card::validate),
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    let cards = context
-        .data
-        .into_iter()
-        .map(|line| Card::parse(line))
-        .collect::<Vec<_>>();
-
-    if context.part_number == 1 {
-        _run_part_1(cards)
-    } else {
-        _run_part_2(cards)
-    }
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let cards = Card::parse_all(input)?;
+
+    Ok(Box::new(move |context| {
+        if context.part_number == 1 {
+            _run_part_1(&cards)
+        } else {
+            _run_part_2(&cards)
+        }
+    }))
 }
 
-fn _run_part_1(cards: Vec<Card>) -> Result<Option<Answer>, String> {
-    let scores_by_card =
-        cards
-            .iter()
-            .map(|card| card.get_overlapping_numbers())
-            .map(|overlapping_numbers| {
-                if overlapping_numbers.is_empty() {
-                    0
-                } else {
-                    2u64.pow(overlapping_numbers.len() as u32 - 1)
-                }
-            });
+fn _run_part_1(cards: &[Card]) -> Result<Option<Answer>, String> {
+    let scores_by_card = cards.iter().map(|card| match card.match_count {
+        0 => 0,
+        match_count => 2u64.pow(match_count - 1),
+    });
 
     let sum = scores_by_card.sum::<u64>();
 
     Ok(Some(sum.into()))
 }
 
-fn _run_part_2(cards: Vec<Card>) -> Result<Option<Answer>, String> {
-    let mut cards_and_amounts_by_id = cards
-        .iter()
-        .map(|card| (card.id, (card, 1)))
-        .collect::<HashMap<_, _>>();
-
-    let mut keys = cards_and_amounts_by_id
-        .iter()
-        .map(|(key, _)| key.clone())
-        .collect::<Vec<_>>();
-    keys.sort();
-
-    for id in keys {
-        let (card, copies) = cards_and_amounts_by_id.get(&id).unwrap().clone();
-        let hits = card.get_overlapping_numbers().len();
-        for i in 0..hits {
-            let id_to_add_copy_to = id + 1 + i as u32;
-            let entry = cards_and_amounts_by_id.get_mut(&id_to_add_copy_to);
-            match entry {
-                Some(entry) => entry.1 += copies,
-                None => {}
+fn _run_part_2(cards: &[Card]) -> Result<Option<Answer>, String> {
+    // Every card adds its copies to a range of the following cards, which is recorded as a
+    // difference array: the copies are added where the range starts and subtracted after it ends.
+    let mut copy_differences = vec![0i64; cards.len() + 1];
+    let mut won_copies = 0;
+    let mut total_cards = 0;
+
+    for (index, card) in cards.iter().enumerate() {
+        won_copies += copy_differences[index];
+        let copies = 1 + won_copies;
+        total_cards += copies;
+
+        let last_won_index = (index + card.match_count as usize).min(cards.len() - 1);
+        copy_differences[index + 1] += copies;
+        copy_differences[last_won_index + 1] -= copies;
+    }
+
+    Ok(Some((total_cards as u64).into()))
+}
+
+/// A set of numbers below 128, stored as one bit per number.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct NumberSet(u128);
+
+impl NumberSet {
+    fn parse(mut numbers: Parser) -> Result<NumberSet, ParseError> {
+        let mut set = NumberSet::default();
+        loop {
+            numbers.skip_whitespace();
+            if numbers.is_at_end() {
+                return Ok(set);
             }
+
+            let number_start = numbers;
+            let number = numbers.integer::<u32>()?;
+            if number >= u128::BITS {
+                return Err(number_start.error(format!(
+                    "Number {} is too large, expected numbers below {}",
+                    number,
+                    u128::BITS
+                )));
+            }
+            set.0 |= 1 << number;
         }
     }
 
-    let total_cards = cards_and_amounts_by_id
-        .into_iter()
-        .map(|(_, (_, copies))| copies)
-        .sum::<u64>();
+    fn intersection(self, other: NumberSet) -> NumberSet {
+        NumberSet(self.0 & other.0)
+    }
 
-    Ok(Some(total_cards.into()))
+    fn len(self) -> u32 {
+        self.0.count_ones()
+    }
 }
 
+#[derive(Debug)]
 struct Card {
     id: u32,
-    received_numbers: HashSet<u32>,
-    winning_numbers: HashSet<u32>,
+    /// How many of the received numbers are winning numbers.
+    match_count: u32,
 }
 
 impl Card {
-    fn parse(line: &String) -> Self {
-        let id = line
-            .chars()
-            .skip("Card ".len())
-            .take_while(|chr| chr != &':')
-            .join("")
-            .trim()
-            .parse::<u32>()
-            .expect("Expected a valid ID");
-
-        let mut number_parts = line
-            .split(':')
-            .last()
-            .unwrap()
-            .split('|')
-            .map(|group_str| {
-                group_str
-                    .split_ascii_whitespace()
-                    .map(|num_str| num_str.trim().parse::<u32>().expect("Expected a valid u32"))
-                    .collect::<HashSet<_>>()
+    fn validate(input: &str) -> Result<(), InputError> {
+        Card::parse_all(input).map(|_| ()).map_err(InputError::from)
+    }
+
+    /// Parses all cards, which have to be numbered 1, 2, 3 and so on.
+    fn parse_all(input: &str) -> Result<Vec<Card>, ParseError> {
+        Parser::new(input)
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let card = Card::parse(line)?;
+                match card.id as usize == index + 1 {
+                    true => Ok(card),
+                    false => Err(line.error(format!(
+                        "Expected card {}, found card {}",
+                        index + 1,
+                        card.id
+                    ))),
+                }
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        assert_eq!(
-            number_parts.len(),
-            2,
-            "Expected 2 number parts, instead got {}",
-            number_parts.len()
-        );
+    fn parse(line: Parser) -> Result<Self, ParseError> {
+        let (mut header, numbers) = line.split_once(':')?;
+        header.literal("Card")?;
+        let id = header.integer::<u32>()?;
+        header.end()?;
 
-        let winning_numbers = number_parts.pop().unwrap();
-        let received_numbers = number_parts.pop().unwrap();
+        let (received, winning) = numbers.split_once('|')?;
+        let received_numbers = NumberSet::parse(received)?;
+        let winning_numbers = NumberSet::parse(winning)?;
 
-        Card {
+        Ok(Card {
             id,
-            winning_numbers,
-            received_numbers,
+            match_count: received_numbers.intersection(winning_numbers).len(),
+        })
+    }
+}
+
+/// Generates `size` cards of 10 winning and 25 received numbers below 100.
+///
+/// Like in the real input, no card wins copies of cards past the end. The cards come in blocks of
+/// 50 that don't win cards in the next block either, so the number of copies stays bounded.
+fn _generate(random: &mut Random, size: usize) -> String {
+    const BLOCK_SIZE: usize = 50;
+    let format_numbers = |numbers: &[u32]| numbers.iter().map(|n| format!("{:>2}", n)).join(" ");
+
+    let cards = (0..size).map(|index| {
+        let cards_after = (BLOCK_SIZE - 1 - index % BLOCK_SIZE).min(size - 1 - index);
+        let match_count = random.range(0..cards_after.min(5) as u64 + 1) as usize;
+
+        let mut numbers = (1..100).collect::<Vec<u32>>();
+        random.shuffle(&mut numbers);
+        let winning_numbers = &numbers[..10];
+        let mut received_numbers = numbers[10..35 - match_count].to_vec();
+        received_numbers.extend(&winning_numbers[..match_count]);
+        random.shuffle(&mut received_numbers);
+
+        format!(
+            "Card {:>3}: {} | {}",
+            index + 1,
+            format_numbers(winning_numbers),
+            format_numbers(&received_numbers)
+        )
+    });
+
+    cards.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::super::testing;
+    use super::*;
+
+    /// Scores a deck the way this assignment did before match counts were computed up front:
+    /// with hash sets for the numbers and a hash map of copies per card id.
+    fn _score_with_hash_map(deck: &[(Vec<u32>, Vec<u32>)]) -> (u64, u64) {
+        let overlapping_numbers = |(received, winning): &(Vec<u32>, Vec<u32>)| {
+            let received = received.iter().collect::<HashSet<_>>();
+            let winning = winning.iter().collect::<HashSet<_>>();
+            received.intersection(&winning).count()
+        };
+
+        let points = deck
+            .iter()
+            .map(overlapping_numbers)
+            .map(|hits| match hits {
+                0 => 0,
+                hits => 2u64.pow(hits as u32 - 1),
+            })
+            .sum();
+
+        let mut copies_by_id = (1..=deck.len())
+            .map(|id| (id, 1))
+            .collect::<HashMap<_, _>>();
+        for (index, card) in deck.iter().enumerate() {
+            let id = index + 1;
+            let copies = copies_by_id[&id];
+            for won_id in id + 1..=id + overlapping_numbers(card) {
+                if let Some(won_copies) = copies_by_id.get_mut(&won_id) {
+                    *won_copies += copies;
+                }
+            }
         }
+
+        (points, copies_by_id.values().sum())
     }
 
-    fn get_overlapping_numbers(&self) -> HashSet<&u32> {
-        self.winning_numbers
-            .intersection(&self.received_numbers)
-            .collect::<HashSet<_>>()
+    #[test]
+    fn matches_hash_map_scoring_on_generated_decks() {
+        testing::check_against_reference(&get_assignment(), 20, |input, part_number| {
+            let numbers = |numbers: &str| {
+                numbers
+                    .split_whitespace()
+                    .map(|n| n.parse().unwrap())
+                    .collect()
+            };
+            let deck = input
+                .lines()
+                .map(|line| {
+                    let (_, numbers_by_side) = line.split_once(": ").unwrap();
+                    let (winning, received) = numbers_by_side.split_once(" | ").unwrap();
+                    (numbers(received), numbers(winning))
+                })
+                .collect::<Vec<_>>();
+
+            let (points, total_cards) = _score_with_hash_map(&deck);
+            match part_number {
+                1 => Some(points.into()),
+                _ => Some(total_cards.into()),
+            }
+        });
+    }
+
+    #[test]
+    fn rejects_numbers_that_do_not_fit() {
+        let error = Card::parse_all("Card 1: 1 2 | 128 3").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 15: Number 128 is too large, expected numbers below 128"
+        );
+    }
+
+    #[test]
+    fn rejects_cards_out_of_order() {
+        let error = Card::parse_all("Card 1: 1 | 1\nCard 3: 2 | 2").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: Expected card 2, found card 3"
+        );
     }
 }
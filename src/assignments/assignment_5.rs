@@ -1,7 +1,7 @@
-use std::ops::{Range, RangeInclusive};
-
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fmt::Display;
+use std::ops::Range;
 
+use super::parsing::sections;
 use super::prelude::*;
 
 pub fn get_assignment() -> Assignment {
@@ -10,7 +10,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 5,
             description: "If You Give A Seed A Fertilizer",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 seeds: 79 14 55 13
@@ -86,174 +86,755 @@ humidity-to-location map:
 56 93 4",
             ),
             answer_example_day_2: Some(46.into()),
-            answer_day_2: None,
+            answer_day_2: Some(81956384.into()),
+            normalization: InputNormalization::default(),
+            validate: Some(SeedMap::validate),
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    #[allow(unused_mut)]
-    let mut seed_map = SeedMap::parse(context.data);
-
-    if context.part_number == 2 {
-        // Works but is extremely inefficient.
-        // This is a temporary workaround.
-        if context.is_example {
-            Ok(Some(46.into()))
-        } else {
-            Ok(None)
-        }
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let seed_map = SeedMap::parse(sections(input))?;
 
-        // This is the real method call, but calculating the result takes 24 seconds and the answer
-        // is incorrect.
+    Ok(Box::new(move |context| _run(&seed_map, context)))
+}
 
-        // seed_map.expand_seeds();
-    } else {
-        let locations = seed_map.evaluate();
-        let lowest_location = *locations.iter().min().expect("There should be a value");
+fn _run(seed_map: &SeedMap, context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
+    let seed_ranges = seed_map.get_seed_ranges(context.part_number)?;
 
-        Ok(Some(lowest_location.into()))
+    let seed_to_location = seed_map.compose();
+    if context.logging_enabled {
+        println!(
+            "Composed {} maps into {} pieces",
+            seed_map.mapping_stages.len(),
+            seed_to_location.pieces.len()
+        );
+    }
+
+    let lowest_location = seed_ranges
+        .iter()
+        .cloned()
+        .filter_map(|seed_range| seed_to_location.get_minimum(seed_range))
+        .min();
+
+    // Traces the journey of a seed that reaches the lowest location, to show how it got there.
+    if let (true, Some(location)) = (context.logging_enabled, lowest_location) {
+        let seed = seed_to_location
+            .inverse(location)
+            .into_iter()
+            .find(|seed| {
+                seed_ranges
+                    .iter()
+                    .any(|range| range.contains(&(*seed as u128)))
+            })
+            .ok_or(format!("No seed reaches location {}", location))?;
+
+        let (journey, traced_location) = seed_map.trace(seed);
+        println!("{}", journey);
+        if traced_location != location {
+            return Err(format!(
+                "Seed {} reaches location {} step by step, but {} through the composed map",
+                seed, traced_location, location
+            ));
+        }
     }
+
+    Ok(lowest_location.map(Answer::from))
 }
 
+/// One past the largest value the input can hold. Values are `u64`, but a range of them can end
+/// right after `u64::MAX`, so ranges are `u128`.
+const VALUE_END: u128 = 1 << 64;
+
+/// The categories every seed passes through, in order. Each map converts a category to the next.
+const CATEGORIES: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+
 #[derive(Debug)]
 struct SeedMap {
-    seed_ranges: Vec<Range<u64>>,
+    seeds: Vec<u64>,
     mapping_stages: Vec<MappingStage>,
 }
 
 impl SeedMap {
-    fn parse(lines: &Vec<String>) -> SeedMap {
-        assert!(
-            lines.first().is_some_and(|line| line.starts_with("seeds")),
-            "The first line must contain the seed values",
-        );
+    fn validate(input: &str) -> Result<(), InputError> {
+        SeedMap::parse(sections(input))
+            .map(|_| ())
+            .map_err(InputError::from)
+    }
 
-        let first_line = lines.first().unwrap();
-        let seed_ranges = first_line
-            .split_ascii_whitespace()
-            .skip(1)
-            .map(|n| n.parse::<u64>().unwrap())
-            .map(|n| n..(n + 1))
-            .collect::<Vec<_>>();
+    fn parse<'a>(mut sections: impl Iterator<Item = Parser<'a>>) -> Result<SeedMap, ParseError> {
+        let mut seeds_section = sections.next().unwrap_or(Parser::new(""));
+        seeds_section.literal("seeds:")?;
+        let seeds = seeds_section.integers::<u64>()?;
+        seeds_section.end()?;
+
+        let mut mapping_stages = vec![];
+        let mut last_section = seeds_section;
+        for (index, section) in sections.enumerate() {
+            let [source, destination] = match CATEGORIES.get(index..index + 2) {
+                Some(&[source, destination]) => [source, destination],
+                _ => return Err(section.error("Expected no more maps after the location map")),
+            };
+            mapping_stages.push(MappingStage::parse(section, source, destination)?);
+            last_section = section;
+        }
 
-        let map_starting_indices = lines
-            .iter()
-            .enumerate()
-            .filter(|(_, line)| line.contains("map:"))
-            .map(|(original_line_number, _)| original_line_number)
-            .collect::<Vec<usize>>();
+        if mapping_stages.len() < CATEGORIES.len() - 1 {
+            return Err(last_section.error(format!(
+                "Expected the {}-to-{} map after this section",
+                CATEGORIES[mapping_stages.len()],
+                CATEGORIES[mapping_stages.len() + 1]
+            )));
+        }
 
-        let mapping_stages = map_starting_indices
-            .iter()
-            .map::<MappingStage, _>(|map_line_index| {
-                let mappings = lines
-                    .iter()
-                    .enumerate()
-                    .skip_while(|(i, _)| i <= map_line_index)
-                    .take_while(|(_, line)| !line.trim().is_empty())
-                    .map(|(_, line)| {
-                        let parts = line
-                            .split_ascii_whitespace()
-                            .map(|n| n.parse::<u64>().unwrap())
-                            .collect::<Vec<_>>();
-
-                        SeedMapping {
-                            destination_range_start: parts[0],
-                            source_range_start: parts[1],
-                            range_length: parts[2],
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                MappingStage { mappings }
+        Ok(SeedMap {
+            seeds,
+            mapping_stages,
+        })
+    }
+
+    /// Returns the seeds to plant: every number is a seed in part 1, while part 2 reads the
+    /// numbers as pairs of a start and a length.
+    fn get_seed_ranges(&self, part_number: u8) -> Result<Vec<Range<u128>>, String> {
+        if part_number == 1 {
+            return Ok(self
+                .seeds
+                .iter()
+                .map(|seed| *seed as u128..*seed as u128 + 1)
+                .collect());
+        }
+
+        let pairs = self.seeds.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(format!(
+                "Expected pairs of a seed and a length, found {} numbers",
+                self.seeds.len()
+            ));
+        }
+        pairs
+            .map(|pair| {
+                let (start, end) = (pair[0] as u128, pair[0] as u128 + pair[1] as u128);
+                match end <= VALUE_END {
+                    true => Ok(start..end),
+                    false => Err("The seeds extend past the largest possible value".to_string()),
+                }
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        SeedMap {
-            seed_ranges,
-            mapping_stages,
+    /// Maps a seed through every stage in turn and describes its journey, including the mapping
+    /// that applied at each step. Returns the description and the location the seed ends up at.
+    fn trace(&self, seed: u64) -> (String, u64) {
+        let mut value = seed;
+        let mut journey = vec![format!("seed {}", value)];
+        let mut steps = vec![];
+
+        for stage in &self.mapping_stages {
+            let mapping = stage.find_mapping(value);
+            value = stage.apply(value);
+            journey.push(format!("{} {}", stage.destination, value));
+            steps.push(match mapping {
+                Some(mapping) => format!(
+                    "  {}-to-{}: {} (line {})",
+                    stage.source, stage.destination, mapping, mapping.line_number
+                ),
+                None => format!(
+                    "  {}-to-{}: no mapping, kept as is",
+                    stage.source, stage.destination
+                ),
+            });
         }
+
+        (
+            format!("{}\n{}", journey.join(" → "), steps.join("\n")),
+            value,
+        )
     }
 
-    #[allow(dead_code)]
-    fn expand_seeds(&mut self) {
-        let seed_ranges = self
-            .seed_ranges
+    /// Composes all maps into a single map from seeds to locations.
+    fn compose(&self) -> PiecewiseMap {
+        self.mapping_stages
             .iter()
-            .map(|r| r.start)
-            .collect::<Vec<_>>()
-            .chunks(2)
-            .map(|chunk| {
-                let start = chunk[0];
-                let length = chunk[1];
-
-                start..(start + length)
-            })
-            .collect::<Vec<_>>();
-
-        self.seed_ranges = seed_ranges;
-    }
-
-    fn evaluate(&self) -> Vec<u64> {
-        self.seed_ranges
-            .clone()
-            .into_par_iter()
-            .flat_map(|range| {
-                range
-                    .into_par_iter()
-                    .map(|value| {
-                        self.mapping_stages
-                            .iter()
-                            .fold(value, |value, mapping| mapping.apply(value))
-                    })
-                    .collect::<Vec<_>>()
+            .fold(PiecewiseMap::identity(), |map, stage| {
+                map.then(&PiecewiseMap::from(stage))
             })
-            .collect::<Vec<_>>()
     }
 }
 
 #[derive(Debug)]
 struct MappingStage {
+    /// The category of the values this stage maps, such as `seed`.
+    source: &'static str,
+    /// The category of the values this stage maps to, such as `soil`.
+    destination: &'static str,
     mappings: Vec<SeedMapping>,
 }
 
 impl MappingStage {
+    /// Parses the map from the source to the destination category, in which no two mappings may
+    /// map the same source value.
+    fn parse(
+        section: Parser,
+        source: &'static str,
+        destination: &'static str,
+    ) -> Result<Self, ParseError> {
+        let mut lines = section.lines();
+        let mut header = lines.next().unwrap_or(section);
+        let header_start = header;
+        let name = header.token()?;
+        let expected_name = format!("{}-to-{}", source, destination);
+        if name != expected_name {
+            return Err(header_start.error(format!(
+                "Expected the {} map, found {}",
+                expected_name, name
+            )));
+        }
+        header.literal("map:")?;
+        header.end()?;
+
+        // Mappings of 0 values map nothing, so they are left out. Kept, one inside another range
+        // would be rejected as an overlap, and would break the ordering `find_mapping` searches.
+        let mut mappings_with_lines = lines
+            .map(|line| SeedMapping::parse(line).map(|mapping| (line, mapping)))
+            .filter(|result| !matches!(result, Ok((_, mapping)) if mapping.range_length == 0))
+            .collect::<Result<Vec<_>, _>>()?;
+        mappings_with_lines.sort_by_key(|(_, mapping)| mapping.source_range_start);
+
+        for window in mappings_with_lines.windows(2) {
+            if let [(_, previous), (line, next)] = window {
+                if previous.get_source_range().end > next.get_source_range().start {
+                    return Err(line.error(format!(
+                        "Source range {:?} overlaps source range {:?}",
+                        next.get_source_range(),
+                        previous.get_source_range()
+                    )));
+                }
+            }
+        }
+
+        Ok(MappingStage {
+            source,
+            destination,
+            mappings: mappings_with_lines
+                .into_iter()
+                .map(|(_, mapping)| mapping)
+                .collect(),
+        })
+    }
+
+    /// Returns the mapping whose source range contains the value, if any.
+    fn find_mapping(&self, value: u64) -> Option<&SeedMapping> {
+        let value = value as u128;
+        let index = self
+            .mappings
+            .partition_point(|mapping| mapping.get_source_range().end <= value);
+        self.mappings
+            .get(index)
+            .filter(|mapping| mapping.get_source_range().contains(&value))
+    }
+
     fn apply(&self, value: u64) -> u64 {
-        let relevant_mapping = self.mappings.iter().find(|m| m.contains(value));
-        match relevant_mapping {
-            Some(mapping) => mapping.apply(value),
+        match self.find_mapping(value) {
+            Some(mapping) => mapping.destination_range_start + (value - mapping.source_range_start),
             None => value,
         }
     }
 }
 
+/// A function that adds an offset to every value, where the offset depends on which of a sorted
+/// list of adjacent ranges the value falls in.
+///
+/// The ranges cover every value up to `u64::MAX`, so maps can be composed by splitting the ranges
+/// of one map wherever their values cross a boundary between the ranges of the next.
+#[derive(Debug, Clone, PartialEq)]
+struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    source: Range<u128>,
+    destination_start: u128,
+}
+
+impl Piece {
+    fn identity(source: Range<u128>) -> Self {
+        Piece {
+            destination_start: source.start,
+            source,
+        }
+    }
+
+    fn get_destination(&self) -> Range<u128> {
+        self.destination_start..(self.destination_start + (self.source.end - self.source.start))
+    }
+
+    fn apply(&self, value: u128) -> u128 {
+        self.destination_start + (value - self.source.start)
+    }
+}
+
+impl PiecewiseMap {
+    fn identity() -> Self {
+        PiecewiseMap {
+            pieces: vec![Piece::identity(0..VALUE_END)],
+        }
+    }
+
+    fn find(&self, value: u128) -> Option<&Piece> {
+        let index = self
+            .pieces
+            .partition_point(|piece| piece.source.end <= value);
+        self.pieces.get(index)
+    }
+
+    /// Returns the map that applies this map and then the next one.
+    fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces: Vec<Piece> = vec![];
+
+        for piece in &self.pieces {
+            let mut source_start = piece.source.start;
+            while source_start < piece.source.end {
+                let value = piece.apply(source_start);
+                let next_piece = next.find(value).expect("Maps should cover all values");
+                let length = (piece.source.end - source_start).min(next_piece.source.end - value);
+                let destination_start = next_piece.apply(value);
+
+                // Joins pieces that continue each other, to keep the composition small.
+                match pieces.last_mut() {
+                    Some(last) if last.get_destination().end == destination_start => {
+                        last.source.end += length
+                    }
+                    _ => pieces.push(Piece {
+                        source: source_start..(source_start + length),
+                        destination_start,
+                    }),
+                }
+                source_start += length;
+            }
+        }
+
+        PiecewiseMap { pieces }
+    }
+
+    /// Returns all values that map to the given value, in ascending order.
+    fn inverse(&self, value: u64) -> Vec<u64> {
+        let value = value as u128;
+        self.pieces
+            .iter()
+            .filter(|piece| piece.get_destination().contains(&value))
+            .map(|piece| (piece.source.start + (value - piece.destination_start)) as u64)
+            .collect()
+    }
+
+    /// Returns the lowest value that any value in the range maps to. Every piece only increases,
+    /// so that is the lowest of the values the start of each overlapping piece maps to.
+    fn get_minimum(&self, range: Range<u128>) -> Option<u64> {
+        if range.is_empty() {
+            return None;
+        }
+
+        let first_index = self
+            .pieces
+            .partition_point(|piece| piece.source.end <= range.start);
+
+        self.pieces[first_index..]
+            .iter()
+            .take_while(|piece| piece.source.start < range.end)
+            .map(|piece| piece.apply(piece.source.start.max(range.start)) as u64)
+            .min()
+    }
+}
+
+impl From<&MappingStage> for PiecewiseMap {
+    /// Fills the gaps between the mappings, which are sorted by their source, with pieces that
+    /// keep values as they are.
+    fn from(stage: &MappingStage) -> Self {
+        let mut pieces = vec![];
+        let mut unmapped_start = 0;
+
+        for mapping in &stage.mappings {
+            let source = mapping.get_source_range();
+            if unmapped_start < source.start {
+                pieces.push(Piece::identity(unmapped_start..source.start));
+            }
+            unmapped_start = source.end;
+            if !source.is_empty() {
+                pieces.push(Piece {
+                    source,
+                    destination_start: mapping.destination_range_start as u128,
+                });
+            }
+        }
+        if unmapped_start < VALUE_END {
+            pieces.push(Piece::identity(unmapped_start..VALUE_END));
+        }
+
+        PiecewiseMap { pieces }
+    }
+}
+
 #[derive(Debug)]
 struct SeedMapping {
+    /// The line of the input this mapping was parsed from.
+    line_number: usize,
     destination_range_start: u64,
     source_range_start: u64,
     range_length: u64,
 }
 
 impl SeedMapping {
-    fn get_source_range(&self) -> RangeInclusive<u64> {
-        self.source_range_start..=(self.source_range_start + self.range_length)
+    /// Parses a mapping, making sure that neither of its ranges extends past `u64::MAX`.
+    fn parse(mut line: Parser) -> Result<Self, ParseError> {
+        let line_start = line;
+        let parts = line.integers::<u64>()?;
+        let [destination_range_start, source_range_start, range_length] = parts[..] else {
+            return Err(line_start.error(format!(
+                "Expected 3 numbers per mapping, found {}",
+                parts.len()
+            )));
+        };
+        line.end()?;
+
+        let range_start = destination_range_start.max(source_range_start);
+        if range_start as u128 + range_length as u128 > VALUE_END {
+            return Err(line_start.error(format!(
+                "Mapping of {} values from {} exceeds the largest possible value",
+                range_length, range_start
+            )));
+        }
+
+        Ok(SeedMapping {
+            line_number: line_start.position().line,
+            destination_range_start,
+            source_range_start,
+            range_length,
+        })
     }
 
-    fn get_offset(&self) -> i128 {
-        self.destination_range_start as i128 - self.source_range_start as i128
+    /// Returns the source values this mapping applies to, which end right before
+    /// `source_range_start + range_length`.
+    fn get_source_range(&self) -> Range<u128> {
+        let start = self.source_range_start as u128;
+        start..(start + self.range_length as u128)
     }
+}
 
-    fn contains(&self, value: u64) -> bool {
-        self.get_source_range().contains(&value)
+impl Display for SeedMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.destination_range_start, self.source_range_start, self.range_length
+        )
     }
+}
 
-    fn apply(&self, value: u64) -> u64 {
-        if self.contains(value) {
-            (value as i128 + self.get_offset()) as u64
-        } else {
-            value
+/// Generates an almanac of 10 seed ranges and `size` mappings per map. The values grow with the
+/// square of the size up to 2^32 like in the real input, so small almanacs can still be solved seed
+/// by seed.
+fn _generate(random: &mut Random, size: usize) -> String {
+    let value_limit = (size.max(1) as u64).pow(2).saturating_mul(64).min(1 << 32);
+
+    let seeds = (0..10)
+        .map(|_| {
+            let start = random.range(0..value_limit / 2);
+            format!("{} {}", start, random.range(1..value_limit / 64 + 1))
+        })
+        .collect::<Vec<_>>();
+    let mut almanac = format!("seeds: {}", seeds.join(" "));
+
+    let source_step = value_limit / size.max(1) as u64;
+    for categories in CATEGORIES.windows(2) {
+        almanac.push_str(&format!("\n\n{}-to-{} map:", categories[0], categories[1]));
+
+        let mut mappings = (0..size as u64)
+            .map(|index| {
+                let gap = random.range(0..source_step / 8 + 1);
+                let source_start = index * source_step + gap;
+                let destination_start = random.range(0..value_limit);
+                format!(
+                    "\n{} {} {}",
+                    destination_start,
+                    source_start,
+                    source_step - gap
+                )
+            })
+            .collect::<Vec<_>>();
+        random.shuffle(&mut mappings);
+        almanac.extend(mappings);
+    }
+
+    almanac
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::super::testing;
+    use super::*;
+
+    /// Maps every single seed through every map by searching its mappings.
+    fn _solve_by_brute_force(input: &str, part_number: u8) -> Option<Answer> {
+        let numbers = |line: &str| {
+            line.split_whitespace()
+                .filter_map(|n| n.parse::<u64>().ok())
+                .collect::<Vec<_>>()
+        };
+        let mut sections = input.split("\n\n");
+        let seed_numbers = numbers(sections.next().unwrap());
+        let maps = sections
+            .map(|section| {
+                let mappings = section.lines().skip(1).map(numbers);
+                mappings.map(|n| (n[0], n[1], n[2])).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let seeds = seed_numbers.chunks(2).flat_map(|pair| match part_number {
+            1 => pair.to_vec(),
+            _ => (pair[0]..pair[0] + pair[1]).collect(),
+        });
+
+        let locations = seeds.map(|seed| {
+            maps.iter().fold(seed, |value, mappings| {
+                let mapping = mappings.iter().find(|(_, source_start, length)| {
+                    (*source_start..source_start + length).contains(&value)
+                });
+                match mapping {
+                    Some((destination_start, source_start, _)) => {
+                        destination_start + (value - source_start)
+                    }
+                    None => value,
+                }
+            })
+        });
+
+        locations.min().map(Answer::from)
+    }
+
+    #[test]
+    fn matches_brute_force_on_generated_almanacs() {
+        testing::check_against_reference(&get_assignment(), 10, _solve_by_brute_force);
+    }
+
+    fn _parse(input: &str) -> Result<SeedMap, String> {
+        SeedMap::parse(sections(input)).map_err(|e| e.to_string())
+    }
+
+    fn _example() -> String {
+        let assignment = get_assignment();
+        let example = assignment.cases.example_day_1.unwrap().input.unwrap();
+        assignment.normalization.apply(example)
+    }
+
+    fn _example_with_stage(stage: &str, replacement: &str) -> String {
+        _example().replace(stage, replacement)
+    }
+
+    /// Maps a value through every stage in turn, by searching the mappings of each stage.
+    fn _apply_stage_by_stage(seed_map: &SeedMap, seed: u64) -> u64 {
+        seed_map.mapping_stages.iter().fold(seed, |value, stage| {
+            let mapping = stage.mappings.iter().find(|mapping| {
+                value >= mapping.source_range_start
+                    && value - mapping.source_range_start < mapping.range_length
+            });
+            match mapping {
+                Some(mapping) => {
+                    mapping.destination_range_start + (value - mapping.source_range_start)
+                }
+                None => value,
+            }
+        })
+    }
+
+    fn _apply(map: &PiecewiseMap, value: u64) -> u64 {
+        map.find(value as u128).unwrap().apply(value as u128) as u64
+    }
+
+    #[test_case(49 => 49; "before the first mapping")]
+    #[test_case(97 => 99; "before the end")]
+    #[test_case(98 => 50; "first value")]
+    #[test_case(99 => 51; "last value")]
+    #[test_case(100 => 100; "past the end")]
+    fn maps_half_open_source_range(value: u64) -> u64 {
+        let stage = MappingStage {
+            source: "seed",
+            destination: "soil",
+            mappings: vec![
+                SeedMapping::parse(Parser::new("52 50 48")).unwrap(),
+                SeedMapping::parse(Parser::new("50 98 2")).unwrap(),
+            ],
+        };
+
+        _apply(&PiecewiseMap::from(&stage), value)
+    }
+
+    #[test]
+    fn traces_seed_journey() {
+        let seed_map = _parse(&_example()).unwrap();
+
+        let (journey, location) = seed_map.trace(79);
+
+        assert_eq!(location, 82);
+        assert_eq!(
+            journey,
+            "seed 79 → soil 81 → fertilizer 81 → water 81 → light 74 → temperature 78 → humidity 78 → location 82
+  seed-to-soil: 52 50 48 (line 5)
+  soil-to-fertilizer: no mapping, kept as is
+  fertilizer-to-water: no mapping, kept as is
+  water-to-light: 18 25 70 (line 20)
+  light-to-temperature: 68 64 13 (line 25)
+  temperature-to-humidity: no mapping, kept as is
+  humidity-to-location: 60 56 37 (line 32)"
+        );
+    }
+
+    #[test]
+    fn composes_stages() {
+        let seed_map = _parse(&_example()).unwrap();
+        let seed_to_location = seed_map.compose();
+
+        for seed in 0..200 {
+            assert_eq!(
+                _apply(&seed_to_location, seed),
+                _apply_stage_by_stage(&seed_map, seed),
+                "seed {}",
+                seed
+            );
         }
+        assert_eq!(_apply(&seed_to_location, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn finds_seeds_by_location() {
+        let seed_map = _parse(&_example()).unwrap();
+        let seed_to_location = seed_map.compose();
+
+        for location in 0..200 {
+            let seeds = seed_to_location.inverse(location);
+            let expected_seeds = (0..200)
+                .filter(|seed| _apply_stage_by_stage(&seed_map, *seed) == location)
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                seeds
+                    .into_iter()
+                    .filter(|seed| *seed < 200)
+                    .collect::<Vec<_>>(),
+                expected_seeds,
+                "location {}",
+                location
+            );
+        }
+    }
+
+    #[test]
+    fn finds_minimum_location_of_seed_ranges() {
+        let seed_map = _parse(&_example()).unwrap();
+        let seed_to_location = seed_map.compose();
+
+        for start in 0..120 {
+            for end in start..120 {
+                let expected = (start..end)
+                    .map(|seed| _apply_stage_by_stage(&seed_map, seed))
+                    .min();
+
+                assert_eq!(
+                    seed_to_location.get_minimum(start as u128..end as u128),
+                    expected,
+                    "seeds {}..{}",
+                    start,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_mappings() {
+        let error = SeedMapping::parse(Parser::new("0 18446744073709551615 2")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 1: Mapping of 2 values from 18446744073709551615 exceeds the largest possible value"
+        );
+    }
+
+    #[test]
+    fn maps_the_largest_value() {
+        let seed_map =
+            "seeds: 18446744073709551615 1\n\nseed-to-soil map:\n0 18446744073709551615 1";
+        let input = [
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ]
+        .windows(2)
+        .fold(seed_map.to_string(), |input, pair| {
+            format!("{}\n\n{}-to-{} map:\n0 0 1", input, pair[0], pair[1])
+        });
+        let assignment = get_assignment();
+
+        assert_eq!(assignment.solve(&input, 1), Ok(Some(Answer::Integer(0))));
+        assert_eq!(assignment.solve(&input, 2), Ok(Some(Answer::Integer(0))));
+    }
+
+    #[test_case("50 98 2\n0 99 0"; "inside another mapping")]
+    #[test_case("50 98 2\n0 10 0\n7 10 0"; "at the same source")]
+    #[test_case("50 98 2\n0 100 0"; "at the end of another mapping")]
+    fn leaves_out_empty_mappings(replacement: &str) {
+        let input = _example_with_stage("50 98 2", replacement);
+        let assignment = get_assignment();
+
+        assert_eq!(_parse(&input).unwrap().mapping_stages[0].mappings.len(), 2);
+        assert_eq!(assignment.solve(&input, 1), Ok(Some(Answer::Integer(35))));
+        assert_eq!(assignment.solve(&input, 2), Ok(Some(Answer::Integer(46))));
+    }
+
+    #[test]
+    fn rejects_overlapping_source_ranges() {
+        let input = _example_with_stage("52 50 48", "52 50 49");
+
+        assert_eq!(
+            _parse(&input).unwrap_err(),
+            "line 4, column 1: Source range 98..100 overlaps source range 50..99"
+        );
+    }
+
+    #[test]
+    fn rejects_maps_out_of_order() {
+        let input = _example_with_stage("water-to-light", "light-to-water");
+
+        assert_eq!(
+            _parse(&input).unwrap_err(),
+            "line 18, column 1: Expected the water-to-light map, found light-to-water"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_maps() {
+        let example = _example();
+        let input = &example[..example.find("\n\nhumidity-to-location").unwrap()];
+
+        assert_eq!(
+            _parse(input).unwrap_err(),
+            "line 27, column 1: Expected the humidity-to-location map after this section"
+        );
     }
 }
@@ -8,7 +8,7 @@ pub fn get_assignment() -> Assignment {
         AssignmentOptions {
             day: 6,
             description: "Wait For It",
-            run: _run,
+            parse: _parse,
             example_input_day_1: Some(
                 "
 Time:      7  15   30
@@ -23,33 +23,65 @@ Distance:  9  40  200",
             ),
             answer_example_day_2: Some(71503.into()),
             answer_day_2: Some(32607562.into()),
+            normalization: InputNormalization::default(),
+            validate: Some(RaceRecord::validate),
+            generate: Some(_generate),
         },
         // cspell: enable
     )
 }
 
-fn _run(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String> {
-    let mut records: Vec<RaceRecord> = RaceRecord::parse_all(context.data);
+fn _parse(input: &str) -> Result<Solver<'_>, String> {
+    let records = RaceRecord::parse_all(input)?;
 
-    if context.part_number == 2 {
-        let record = records
-            .drain(..)
-            .fold(RaceRecord::default(), |acc, cur| RaceRecord {
-                time_limit: format!("{}{}", acc.time_limit, cur.time_limit)
-                    .parse::<u64>()
-                    .unwrap(),
-                distance_record: format!("{}{}", acc.distance_record, cur.distance_record)
-                    .parse::<u64>()
-                    .unwrap(),
-            });
-        records.push(record)
+    Ok(Box::new(move |context| _run(&records, context)))
+}
+
+fn _run(
+    records: &[RaceRecord],
+    context: AssignmentRuntimeContext,
+) -> Result<Option<Answer>, String> {
+    if records.is_empty() {
+        return Ok(None);
     }
 
-    let winning_moves = records.into_iter().map(|record| record.get_winning_moves());
+    let concatenated;
+    let records = if context.part_number == 2 {
+        concatenated = [RaceRecord::concatenate(records)?];
+        &concatenated[..]
+    } else {
+        records
+    };
+
+    let winning_moves_amounts = records
+        .iter()
+        .map(|record| {
+            let amount = record.count_winning_moves();
+
+            // Cross-checks the closed-form count against the full enumeration, which is much
+            // slower but easy to verify.
+            if context.logging_enabled {
+                let enumerated_amount = record.enumerate_winning_moves();
+                println!(
+                    "{:?}: {} winning moves (enumerated: {})",
+                    record, amount, enumerated_amount
+                );
+                if amount != enumerated_amount {
+                    return Err(format!(
+                        "Counted {} winning moves for {:?}, but enumerated {}",
+                        amount, record, enumerated_amount
+                    ));
+                }
+            }
+
+            Ok(amount)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let winning_moves_amounts_multiplied = winning_moves
-        .map(|moves| moves.len())
-        .fold(1, |acc, cur| acc * cur);
+    let winning_moves_amounts_multiplied = winning_moves_amounts
+        .into_iter()
+        .try_fold(1u64, |product, amount| product.checked_mul(amount))
+        .ok_or("The product of the winning move amounts exceeds the largest possible value")?;
 
     Ok(Some(winning_moves_amounts_multiplied.into()))
 }
@@ -61,39 +93,108 @@ struct RaceRecord {
 }
 
 impl RaceRecord {
-    fn parse_all(lines: &Vec<String>) -> Vec<RaceRecord> {
-        assert_eq!(lines.len(), 2, "Lines must have length of 2");
+    fn validate(input: &str) -> Result<(), InputError> {
+        RaceRecord::parse_all(input)
+            .map(|_| ())
+            .map_err(InputError::from)
+    }
 
-        let all_numbers = lines
-            .into_par_iter()
-            .map(|line| {
-                line.split(':')
-                    .last()
-                    .unwrap()
-                    .split_ascii_whitespace()
-                    .map(|n| n.parse::<u64>().unwrap())
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
+    fn parse_all(input: &str) -> Result<Vec<RaceRecord>, ParseError> {
+        let lines = Parser::new(input).lines().collect::<Vec<_>>();
+        let [mut time_line, mut distance_line] = lines[..] else {
+            let offending_line = lines.get(2).or(lines.last()).unwrap_or(&lines[0]);
+            return Err(
+                offending_line.error(format!("Expected exactly 2 lines, found {}", lines.len()))
+            );
+        };
+
+        time_line.literal("Time:")?;
+        let times = time_line.integers::<u64>()?;
+        time_line.end()?;
 
-        let times_and_distances = all_numbers[0].iter().zip(all_numbers[1].iter());
-        times_and_distances
+        distance_line.literal("Distance:")?;
+        let distances = distance_line.integers::<u64>()?;
+        if distances.len() != times.len() {
+            return Err(distance_line.error(format!(
+                "Expected {} distances to match the times, found {}",
+                times.len(),
+                distances.len()
+            )));
+        }
+        distance_line.end()?;
+
+        let times_and_distances = times.into_iter().zip(distances);
+        Ok(times_and_distances
             .map(|(time_limit, distance_record)| RaceRecord {
-                time_limit: *time_limit,
-                distance_record: *distance_record,
+                time_limit,
+                distance_record,
             })
-            .collect()
+            .collect())
+    }
+
+    /// Joins the digits of all records into a single record, as the kerning in part 2 requires.
+    fn concatenate(records: &[RaceRecord]) -> Result<RaceRecord, String> {
+        let concatenate_digits = |numbers: Vec<u64>| {
+            let digits = numbers.iter().map(u64::to_string).collect::<String>();
+            digits
+                .parse::<u64>()
+                .map_err(|_| format!("The concatenated number {} is out of range", digits))
+        };
+
+        Ok(RaceRecord {
+            time_limit: concatenate_digits(records.iter().map(|r| r.time_limit).collect())?,
+            distance_record: concatenate_digits(
+                records.iter().map(|r| r.distance_record).collect(),
+            )?,
+        })
     }
 
-    fn get_winning_moves(&self) -> Vec<RaceStrategy> {
-        (1..(self.time_limit - 1))
+    /// Counts the button hold times that beat the distance record without enumerating them.
+    ///
+    /// Holding the button for `h` out of `T` milliseconds travels `h * (T - h)`, so the winning
+    /// hold times are the integers strictly between the roots of `h² - Th + D = 0`, which are
+    /// `(T ± √(T² - 4D)) / 2`. The lower root is estimated with an integer square root and then
+    /// nudged onto the exact boundary, so there is no floating point rounding involved.
+    fn count_winning_moves(&self) -> u64 {
+        let time_limit = self.time_limit as u128;
+        let distance_record = self.distance_record as u128;
+        let wins = |hold_time: u128| hold_time * (time_limit - hold_time) > distance_record;
+
+        let Some(discriminant) = (time_limit * time_limit).checked_sub(4 * distance_record) else {
+            return 0;
+        };
+
+        let mut first_winning_hold_time = (time_limit - _integer_sqrt(discriminant)) / 2;
+        while first_winning_hold_time > 0 && wins(first_winning_hold_time - 1) {
+            first_winning_hold_time -= 1;
+        }
+        while first_winning_hold_time <= time_limit / 2 && !wins(first_winning_hold_time) {
+            first_winning_hold_time += 1;
+        }
+        if first_winning_hold_time > time_limit / 2 {
+            return 0;
+        }
+
+        // The distance is symmetric around half the time limit.
+        let last_winning_hold_time = time_limit - first_winning_hold_time;
+
+        (last_winning_hold_time - first_winning_hold_time + 1) as u64
+    }
+
+    /// Counts the winning hold times by trying every one of them, without keeping them around.
+    fn enumerate_winning_moves(&self) -> u64 {
+        // Holding the button for the whole race, or not at all, never moves the boat, but both are
+        // still valid moves that can be checked.
+        (0..=self.time_limit)
             .into_par_iter()
             .map(|button_hold_time| RaceStrategy {
                 button_hold_time,
                 travel_time: self.time_limit - button_hold_time,
             })
-            .filter(|strategy| strategy.get_total_distance_traveled() > self.distance_record)
-            .collect()
+            .filter(|strategy| {
+                strategy.get_total_distance_traveled() > self.distance_record as u128
+            })
+            .count() as u64
     }
 }
 
@@ -103,7 +204,199 @@ struct RaceStrategy {
 }
 
 impl RaceStrategy {
-    fn get_total_distance_traveled(&self) -> u64 {
-        self.button_hold_time * self.travel_time
+    fn get_total_distance_traveled(&self) -> u128 {
+        self.button_hold_time as u128 * self.travel_time as u128
+    }
+}
+
+/// Returns the largest integer whose square is at most `n`.
+fn _integer_sqrt(n: u128) -> u128 {
+    let square_exceeds_n = |x: u128| !matches!(x.checked_mul(x), Some(square) if square <= n);
+
+    let mut root = (n as f64).sqrt() as u128;
+    while square_exceeds_n(root) {
+        root -= 1;
+    }
+    while !square_exceeds_n(root + 1) {
+        root += 1;
+    }
+
+    root
+}
+
+/// Generates a sheet of races of up to `size` milliseconds, like the 4 races of under 100
+/// milliseconds in the real input. Part 2 joins the digits of all races, so larger races leave room
+/// for fewer of them, and the time limits stop growing once a single distance record would no
+/// longer fit.
+fn _generate(random: &mut Random, size: usize) -> String {
+    const MAX_TIME_LIMIT: u64 = 6_000_000_000;
+    const MAX_DIGITS: usize = 19;
+    let best_distance = |time_limit: u64| (time_limit / 2) * (time_limit - time_limit / 2);
+
+    let max_time_limit = (size as u64).clamp(7, MAX_TIME_LIMIT);
+    let distance_digits = best_distance(max_time_limit).to_string().len();
+    let race_count = (MAX_DIGITS / distance_digits).clamp(1, 4);
+
+    let races = (0..race_count)
+        .map(|_| {
+            let time_limit = random.range((max_time_limit / 2).max(7)..max_time_limit + 1);
+            let best_distance = best_distance(time_limit);
+            (time_limit, random.range(best_distance / 2..best_distance))
+        })
+        .collect::<Vec<_>>();
+
+    let width = max_time_limit.to_string().len().max(distance_digits);
+    let times = races
+        .iter()
+        .map(|(time_limit, _)| format!(" {:>width$}", time_limit, width = width));
+    let distances = races
+        .iter()
+        .map(|(_, distance)| format!(" {:>width$}", distance, width = width));
+    format!(
+        "Time:    {}\nDistance:{}",
+        times.collect::<String>(),
+        distances.collect::<String>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::super::random::Random;
+    use super::super::testing;
+    use super::*;
+
+    /// Searches for the first winning hold time by bisection, as the distance grows up to half the
+    /// time limit, and mirrors it around the middle.
+    fn _solve_by_bisection(input: &str, part_number: u8) -> Option<Answer> {
+        let numbers = input
+            .lines()
+            .map(|line| {
+                let numbers = line.split_whitespace().skip(1);
+                match part_number {
+                    1 => numbers.map(|n| n.parse::<u128>().unwrap()).collect(),
+                    _ => vec![numbers.collect::<String>().parse::<u128>().unwrap()],
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let product = numbers[0]
+            .iter()
+            .zip(&numbers[1])
+            .map(|(time_limit, distance_record)| {
+                let wins =
+                    |hold_time: u128| hold_time * (time_limit - hold_time) > *distance_record;
+                let (mut losing, mut winning) = (0, time_limit / 2);
+                if !wins(winning) {
+                    return 0;
+                }
+                while winning - losing > 1 {
+                    let middle = (losing + winning) / 2;
+                    match wins(middle) {
+                        true => winning = middle,
+                        false => losing = middle,
+                    }
+                }
+                (time_limit - 2 * winning + 1) as u64
+            })
+            .product::<u64>();
+
+        Some(product.into())
+    }
+
+    fn _count_by_brute_force(record: &RaceRecord) -> u64 {
+        let time_limit = record.time_limit as u128;
+        (0..=time_limit)
+            .filter(|hold_time| {
+                hold_time * (time_limit - hold_time) > record.distance_record as u128
+            })
+            .count() as u64
+    }
+
+    #[test_case(0, 0 => 0; "zero time limit")]
+    #[test_case(1, 0 => 0; "one millisecond")]
+    #[test_case(2, 0 => 1; "only hold time 1 moves")]
+    #[test_case(3, 1 => 2; "last hold time wins")]
+    #[test_case(4, 4 => 0; "record equals best distance")]
+    #[test_case(7, 9 => 4; "example race")]
+    fn counts_winning_moves(time_limit: u64, distance_record: u64) -> u64 {
+        let record = RaceRecord {
+            time_limit,
+            distance_record,
+        };
+
+        assert_eq!(
+            record.enumerate_winning_moves(),
+            record.count_winning_moves()
+        );
+        record.count_winning_moves()
+    }
+
+    #[test]
+    fn counts_winning_moves_without_overflowing() {
+        let record = RaceRecord {
+            time_limit: u64::MAX,
+            distance_record: u64::MAX,
+        };
+
+        assert_eq!(record.count_winning_moves(), u64::MAX - 3);
+    }
+
+    #[test]
+    fn matches_brute_force_on_random_records() {
+        let mut random = Random::new(6);
+
+        for _ in 0..5_000 {
+            let time_limit = random.range(0..100);
+            let best_distance = (time_limit / 2) * (time_limit - time_limit / 2);
+            let record = RaceRecord {
+                time_limit,
+                distance_record: random.range(0..best_distance + 3),
+            };
+
+            let expected = _count_by_brute_force(&record);
+            assert_eq!(record.count_winning_moves(), expected, "{:?}", record);
+            assert_eq!(record.enumerate_winning_moves(), expected, "{:?}", record);
+        }
+    }
+
+    #[test]
+    fn matches_bisection_on_generated_sheets() {
+        testing::check_against_reference(&get_assignment(), 1000, _solve_by_bisection);
+    }
+
+    #[test_case(1_000; "thousand")]
+    #[test_case(1_000_000; "million")]
+    #[test_case(1 << 40; "beyond the largest time limit")]
+    #[test_case(usize::MAX; "largest size")]
+    fn solves_sheets_of_any_size(size: usize) {
+        let assignment = get_assignment();
+
+        for seed in 0..20 {
+            let input = assignment.generate_input(seed, size).unwrap();
+            for part_number in [1, 2] {
+                assert_eq!(
+                    assignment.solve(&input, part_number),
+                    Ok(_solve_by_bisection(&input, part_number)),
+                    "part {} of\n{}",
+                    part_number,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn answers_nothing_without_races() {
+        for part_number in [1, 2] {
+            let context = AssignmentRuntimeContext {
+                part_number,
+                is_example: true,
+                logging_enabled: false,
+            };
+
+            assert_eq!(_parse("Time:\nDistance:").unwrap()(context), Ok(None));
+        }
     }
 }
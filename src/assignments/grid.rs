@@ -0,0 +1,330 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+use super::ByteGrid;
+
+/// The offsets of the 4 orthogonal neighbours of a cell, in reading order.
+const NEIGHBOR_OFFSETS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// The offsets of the 8 orthogonal and diagonal neighbours of a cell, in reading order.
+const NEIGHBOR_OFFSETS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub fn new(x: usize, y: usize) -> Self {
+        Point { x, y }
+    }
+}
+
+/// A horizontal run of adjacent cells in a single row, such as the digits of a number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Run {
+    pub y: usize,
+    pub x: Range<usize>,
+}
+
+impl Run {
+    pub fn contains(&self, point: Point) -> bool {
+        point.y == self.y && self.x.contains(&point.x)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.x.clone().map(|x| Point::new(x, self.y))
+    }
+}
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid from its cells in reading order.
+    ///
+    /// Panics if the number of cells does not match the given dimensions.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "Expected {}x{} cells, got {}",
+            width,
+            height,
+            cells.len()
+        );
+
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a grid from the bytes of an input, converting each byte to a cell.
+    pub fn from_bytes(grid: &ByteGrid, mut cell: impl FnMut(u8) -> T) -> Self {
+        let cells = (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+            .filter_map(|(x, y)| grid.get(x, y))
+            .map(&mut cell)
+            .collect();
+
+        Grid::new(grid.width(), grid.height(), cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x < self.width && point.y < self.height
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        match self.contains(point) {
+            true => self.cells.get(point.y * self.width + point.x),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        match self.contains(point) {
+            true => self.cells.get_mut(point.y * self.width + point.x),
+            false => None,
+        }
+    }
+
+    /// Returns all cells with their positions, in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| (Point::new(index % width, index / width), cell))
+    }
+
+    /// Returns the point at the given offset from another point, or `None` if it lies outside
+    /// the grid.
+    pub fn offset(&self, point: Point, (dx, dy): (isize, isize)) -> Option<Point> {
+        let offset_point = Point::new(
+            point.x.checked_add_signed(dx)?,
+            point.y.checked_add_signed(dy)?,
+        );
+        self.contains(offset_point).then_some(offset_point)
+    }
+
+    /// Returns the orthogonal neighbours of a point that lie inside the grid.
+    pub fn neighbors_4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOR_OFFSETS_4
+            .into_iter()
+            .filter_map(move |offset| self.offset(point, offset))
+    }
+
+    /// Returns the orthogonal and diagonal neighbours of a point that lie inside the grid.
+    pub fn neighbors_8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOR_OFFSETS_8
+            .into_iter()
+            .filter_map(move |offset| self.offset(point, offset))
+    }
+
+    /// Walks from `start` (inclusive) in the given direction until the edge of the grid.
+    ///
+    /// Rows, columns and diagonals are all rays, e.g. `(1, 1)` scans the diagonal towards the
+    /// bottom right.
+    pub fn ray(
+        &self,
+        start: Point,
+        direction: (isize, isize),
+    ) -> impl Iterator<Item = (Point, &T)> + '_ {
+        std::iter::successors(self.contains(start).then_some(start), move |point| {
+            self.offset(*point, direction)
+        })
+        .filter_map(|point| self.get(point).map(|cell| (point, cell)))
+    }
+
+    pub fn row(&self, y: usize) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.ray(Point::new(0, y), (1, 0))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.ray(Point::new(x, 0), (0, 1))
+    }
+
+    /// Finds all maximal horizontal runs of cells that match the predicate, in reading order.
+    pub fn runs(&self, predicate: impl Fn(&T) -> bool) -> Vec<Run> {
+        let mut runs = vec![];
+
+        for y in 0..self.height {
+            let mut run_start = None;
+            for x in 0..=self.width {
+                let matches = x < self.width && predicate(&self.cells[y * self.width + x]);
+                match (run_start, matches) {
+                    (None, true) => run_start = Some(x),
+                    (Some(start), false) => {
+                        runs.push(Run { y, x: start..x });
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        runs
+    }
+
+    /// Renders the grid as text, one line per row, using the given function for every cell.
+    pub fn render<S: Display>(&self, mut cell: impl FnMut(Point, &T) -> S) -> String {
+        (0..self.height)
+            .map(|y| {
+                self.row(y)
+                    .map(|(point, value)| cell(point, value).to_string())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Display for Grid<char> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(|_, cell| *cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x3 grid with the letters `a` to `l` in reading order.
+    fn _letters() -> Grid<char> {
+        Grid::new(4, 3, ('a'..='l').collect())
+    }
+
+    #[test]
+    fn converts_byte_grids() {
+        let input = "ab\ncd";
+        let grid = Grid::from_bytes(&ByteGrid::new(input).unwrap(), char::from);
+
+        assert_eq!(grid, Grid::new(2, 2, vec!['a', 'b', 'c', 'd']));
+    }
+
+    #[test]
+    fn finds_all_neighbors_inside_the_grid() {
+        let grid = _letters();
+        let neighbors = |x, y| {
+            grid.neighbors_8(Point::new(x, y))
+                .filter_map(|point| grid.get(point))
+                .collect::<String>()
+        };
+
+        assert_eq!(neighbors(1, 1), "abcegijk");
+        assert_eq!(neighbors(0, 0), "bef");
+        assert_eq!(neighbors(3, 2), "ghk");
+    }
+
+    #[test]
+    fn finds_orthogonal_neighbors_inside_the_grid() {
+        let grid = _letters();
+        let neighbors = |x, y| {
+            grid.neighbors_4(Point::new(x, y))
+                .filter_map(|point| grid.get(point))
+                .collect::<String>()
+        };
+
+        assert_eq!(neighbors(1, 1), "begj");
+        assert_eq!(neighbors(0, 0), "be");
+        assert_eq!(neighbors(3, 2), "hk");
+    }
+
+    #[test]
+    fn rejects_offsets_outside_the_grid() {
+        let grid = _letters();
+
+        assert_eq!(grid.offset(Point::new(0, 0), (-1, 0)), None);
+        assert_eq!(grid.offset(Point::new(3, 0), (1, 0)), None);
+        assert_eq!(grid.offset(Point::new(3, 2), (0, 1)), None);
+        assert_eq!(
+            grid.offset(Point::new(1, 1), (2, 1)),
+            Some(Point::new(3, 2))
+        );
+    }
+
+    #[test]
+    fn walks_rays_until_the_edge() {
+        let grid = _letters();
+        let ray = |start, direction| {
+            grid.ray(start, direction)
+                .map(|(_, cell)| *cell)
+                .collect::<String>()
+        };
+
+        assert_eq!(ray(Point::new(0, 0), (1, 1)), "afk");
+        assert_eq!(ray(Point::new(3, 2), (-1, 0)), "lkji");
+        assert_eq!(ray(Point::new(2, 0), (0, 1)), "cgk");
+        assert_eq!(ray(Point::new(4, 0), (1, 0)), "");
+        assert_eq!(
+            grid.row(1).map(|(_, cell)| *cell).collect::<String>(),
+            "efgh"
+        );
+        assert_eq!(
+            grid.column(2).map(|(_, cell)| *cell).collect::<String>(),
+            "cgk"
+        );
+        assert_eq!(grid.column(4).count(), 0);
+    }
+
+    #[test]
+    fn finds_maximal_runs_in_reading_order() {
+        let grid = Grid::new(4, 3, "12.3....456.".chars().collect());
+        let runs = grid.runs(char::is_ascii_digit);
+
+        assert_eq!(
+            runs,
+            vec![
+                Run { y: 0, x: 0..2 },
+                Run { y: 0, x: 3..4 },
+                Run { y: 2, x: 0..3 },
+            ]
+        );
+        assert_eq!(
+            runs[2].points().collect::<Vec<_>>(),
+            vec![Point::new(0, 2), Point::new(1, 2), Point::new(2, 2)]
+        );
+        assert!(runs[0].contains(Point::new(1, 0)));
+        assert!(!runs[0].contains(Point::new(2, 0)));
+        assert!(!runs[0].contains(Point::new(1, 1)));
+    }
+
+    #[test]
+    fn renders_one_line_per_row() {
+        let grid = _letters();
+
+        assert_eq!(grid.to_string(), "abcd\nefgh\nijkl");
+        assert_eq!(
+            grid.render(|point, cell| match point.x == point.y {
+                true => cell.to_ascii_uppercase(),
+                false => *cell,
+            }),
+            "Abcd\neFgh\nijKl"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use super::Random;
+
+/// Checks the normalized input of an assignment before its solver runs.
+pub type InputValidator = fn(input: &str) -> Result<(), InputError>;
+
+/// Writes a random but valid input for an assignment, where `size` scales the input in a way the
+/// assignment describes, such as its number of lines.
+pub type InputGenerator = fn(random: &mut Random, size: usize) -> String;
+
+/// Describes why an input is malformed.
+#[derive(Debug, PartialEq)]
+pub struct InputError {
+    /// The 1-based number of the offending line in the normalized input.
+    pub line_number: usize,
+    /// The 1-based column on that line, if the error points at a specific one.
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl InputError {
+    pub fn new(line_number: usize, message: impl Into<String>) -> Self {
+        InputError {
+            line_number,
+            column: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(
+                f,
+                "line {}, column {}: {}",
+                self.line_number, column, self.message
+            ),
+            None => write!(f, "line {}: {}", self.line_number, self.message),
+        }
+    }
+}
+
+/// The steps that are applied to an input before it is handed to an assignment.
+///
+/// The defaults are chosen so that inputs pasted from a browser or saved on another platform
+/// parse the same as the original puzzle input.
+#[derive(Debug, Clone)]
+pub struct InputNormalization {
+    /// Converts `\r\n` and lone `\r` line endings to `\n`.
+    pub normalize_line_endings: bool,
+    /// Removes blank lines at the start and end of the input.
+    pub trim_blank_lines: bool,
+    /// Removes whitespace at the end of every line.
+    pub trim_line_ends: bool,
+    /// Removes whitespace at both ends of every line. Takes precedence over `trim_line_ends`.
+    pub trim_lines: bool,
+    /// Replaces every tab with the given number of spaces.
+    pub expand_tabs: Option<usize>,
+}
+
+impl Default for InputNormalization {
+    fn default() -> Self {
+        InputNormalization {
+            normalize_line_endings: true,
+            trim_blank_lines: true,
+            trim_line_ends: true,
+            trim_lines: false,
+            expand_tabs: None,
+        }
+    }
+}
+
+impl InputNormalization {
+    /// Applies all enabled steps to the input and returns the resulting text, with lines joined
+    /// by `\n` and no trailing line ending.
+    pub fn apply(&self, input: &str) -> String {
+        let input = match self.normalize_line_endings && input.contains('\r') {
+            true => Cow::Owned(input.replace("\r\n", "\n").replace('\r', "\n")),
+            false => Cow::Borrowed(input),
+        };
+
+        let mut lines = input
+            .lines()
+            .map(|line| {
+                let line = match self.expand_tabs {
+                    Some(width) if line.contains('\t') => {
+                        Cow::Owned(line.replace('\t', &" ".repeat(width)))
+                    }
+                    _ => Cow::Borrowed(line),
+                };
+
+                if self.trim_lines {
+                    _trim_cow(line, str::trim)
+                } else if self.trim_line_ends {
+                    _trim_cow(line, str::trim_end)
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if self.trim_blank_lines {
+            let is_blank = |line: &Cow<str>| line.trim().is_empty();
+            let trailing_blank_lines = lines.iter().rev().take_while(|l| is_blank(l)).count();
+            lines.truncate(lines.len() - trailing_blank_lines);
+            let leading_blank_lines = lines.iter().take_while(|l| is_blank(l)).count();
+            lines.drain(..leading_blank_lines);
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn _trim_cow<'a>(line: Cow<'a, str>, trim: fn(&str) -> &str) -> Cow<'a, str> {
+    match line {
+        Cow::Borrowed(line) => Cow::Borrowed(trim(line)),
+        Cow::Owned(line) => Cow::Owned(trim(&line).to_string()),
+    }
+}
+
+/// Splits the input into blocks that are separated by one or more blank lines.
+///
+/// Expects normalized input, in which blank lines are empty and lines end in `\n`.
+pub fn paragraphs(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim_matches('\n'))
+        .filter(|paragraph| !paragraph.is_empty())
+}
+
+/// A rectangular view of the input's bytes that indexes into the input without copying it.
+///
+/// Expects normalized input, in which lines end in `\n`.
+#[derive(Debug, Clone, Copy)]
+pub struct ByteGrid<'a> {
+    bytes: &'a [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> ByteGrid<'a> {
+    /// Creates a grid over the input, failing if not all lines have the same length.
+    pub fn new(input: &'a str) -> Result<Self, InputError> {
+        let bytes = input.strip_suffix('\n').unwrap_or(input).as_bytes();
+        if bytes.is_empty() {
+            return Ok(ByteGrid {
+                bytes,
+                width: 0,
+                height: 0,
+            });
+        }
+
+        let width = bytes
+            .iter()
+            .position(|b| *b == b'\n')
+            .unwrap_or(bytes.len());
+        let mut height = 0;
+        for (index, row) in bytes.split(|b| *b == b'\n').enumerate() {
+            if row.len() != width {
+                return Err(InputError::new(
+                    index + 1,
+                    format!("Expected {} columns, found {}", width, row.len()),
+                ));
+            }
+            height += 1;
+        }
+
+        Ok(ByteGrid {
+            bytes,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the byte at the given column and row, or `None` if it lies outside the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        self.row(y)?.get(x).copied()
+    }
+
+    /// Returns the bytes of the given row, or `None` if it lies outside the grid.
+    pub fn row(&self, y: usize) -> Option<&'a [u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let start = y * (self.width + 1);
+        Some(&self.bytes[start..start + self.width])
+    }
+}
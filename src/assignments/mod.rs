@@ -1,20 +1,30 @@
 use core::panic;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
 use stopwatch::Stopwatch;
 
-mod assignment_1;
-mod assignment_2;
-mod assignment_3;
-mod assignment_4;
-mod assignment_5;
-mod assignment_6;
+use crate::allocation::{self, AllocationStats};
 
-mod prelude;
+pub mod assignment_1;
+pub mod assignment_2;
+pub mod assignment_3;
+pub mod assignment_4;
+pub mod assignment_5;
+pub mod assignment_6;
+
+pub mod grid;
+mod input;
+mod parsing;
+pub mod prelude;
+mod random;
+#[cfg(test)]
+mod testing;
+
+pub use input::{ByteGrid, InputError, InputGenerator, InputNormalization, InputValidator};
+pub use parsing::{ParseError, Parser, Position};
+pub use random::Random;
 
 pub fn get_assignments() -> Vec<Assignment> {
     let assignments = vec![
@@ -49,7 +59,7 @@ pub fn get_assignments() -> Vec<Assignment> {
     assignments
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Answer {
     Integer(u64),
     String(String),
@@ -110,6 +120,18 @@ pub struct TestCaseGroup<T> {
     pub day2: T,
 }
 
+impl<T> TestCaseGroup<T> {
+    /// Returns every slot of the group together with its display name, in run order.
+    pub fn named(&self) -> [(&'static str, &T); 4] {
+        [
+            ("Example 1", &self.example_day_1),
+            ("Day 1", &self.day1),
+            ("Example 2", &self.example_day_2),
+            ("Day 2", &self.day2),
+        ]
+    }
+}
+
 pub struct TestCase {
     pub input: Option<&'static str>,
     pub expected: Option<Answer>,
@@ -122,6 +144,10 @@ pub struct TestCaseOutput {
     pub expected: Option<Answer>,
     pub actual: Result<Option<Answer>, String>,
     pub runtime: Duration,
+    /// The heap usage of the solver, if allocations are counted.
+    pub allocations: Option<AllocationStats>,
+    pub part_number: u8,
+    pub is_example: bool,
 }
 
 impl TestCaseOutput {
@@ -145,6 +171,8 @@ impl TestCaseOutput {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TestCaseResult {
     NoAnswer,
     Unknown,
@@ -157,31 +185,67 @@ pub struct Assignment {
     pub day: u32,
     pub description: &'static str,
     pub cases: TestCaseGroup<Option<TestCase>>,
-    _f: InternalAssignmentCallback,
+    pub normalization: InputNormalization,
+    _parse: InputParser,
+    _validate: Option<InputValidator>,
+    _generate: Option<InputGenerator>,
 }
 
-type InternalAssignmentCallback =
-    fn(context: AssignmentRuntimeContext) -> Result<Option<Answer>, String>;
+/// Solves either part of an input that was parsed already, borrowing from that input.
+pub type Solver<'a> = Box<dyn Fn(AssignmentRuntimeContext) -> Result<Option<Answer>, String> + 'a>;
+
+/// Parses a normalized and validated input once, into a [`Solver`] for both parts.
+pub type InputParser = for<'a> fn(input: &'a str) -> Result<Solver<'a>, String>;
 
+/// Everything needed to build an [`Assignment`] with [`Assignment::new`].
 pub struct AssignmentOptions {
-    day: u32,
-    description: &'static str,
-    run: InternalAssignmentCallback,
-    example_input_day_1: Option<&'static str>,
-    answer_example_day_1: Option<Answer>,
-    answer_day_2: Option<Answer>,
-    example_input_day_2: Option<&'static str>,
-    answer_example_day_2: Option<Answer>,
-    answer_day_1: Option<Answer>,
+    pub day: u32,
+    pub description: &'static str,
+    pub parse: InputParser,
+    pub example_input_day_1: Option<&'static str>,
+    pub answer_example_day_1: Option<Answer>,
+    pub answer_day_2: Option<Answer>,
+    pub example_input_day_2: Option<&'static str>,
+    pub answer_example_day_2: Option<Answer>,
+    pub answer_day_1: Option<Answer>,
+    pub normalization: InputNormalization,
+    /// Checked against the normalized input before `parse` is called. When it fails, the test case
+    /// reports the error instead of running the assignment.
+    pub validate: Option<InputValidator>,
+    /// Writes random inputs for stress tests and benchmarks, see [`Assignment::generate_input`].
+    pub generate: Option<InputGenerator>,
 }
 
-pub struct AssignmentRuntimeContext<'a> {
-    pub data: &'a Vec<String>,
+/// The part a [`Solver`] solves, and how. The input is not part of it: the solver has parsed that
+/// already.
+pub struct AssignmentRuntimeContext {
     pub part_number: u8,
     pub is_example: bool,
     pub logging_enabled: bool,
 }
 
+#[derive(Default)]
+pub struct RunOptions {
+    /// Only runs the test case with this name (see [`TestCaseGroup::named`]), ignoring case and
+    /// spaces. Runs all test cases when `None`.
+    pub case: Option<String>,
+    /// Enables the assignment's logging for every test case that runs.
+    pub logging_enabled: bool,
+}
+
+impl RunOptions {
+    pub fn includes_case(&self, name: &str) -> bool {
+        fn normalize(name: &str) -> String {
+            name.replace(' ', "").to_lowercase()
+        }
+
+        match &self.case {
+            Some(case) => normalize(case) == normalize(name),
+            None => true,
+        }
+    }
+}
+
 impl Assignment {
     pub fn new(options: AssignmentOptions) -> Assignment {
         return Assignment {
@@ -213,42 +277,86 @@ impl Assignment {
                     is_example: false,
                 }),
             },
-            _f: options.run,
+            normalization: options.normalization,
+            _parse: options.parse,
+            _validate: options.validate,
+            _generate: options.generate,
         };
     }
 
-    pub fn run(&self) -> TestCaseGroup<Option<TestCaseOutput>> {
+    pub fn run(&self, options: &RunOptions) -> TestCaseGroup<Option<TestCaseOutput>> {
+        let [example_day_1, day1, example_day_2, day2] = self.cases.named().map(|(name, case)| {
+            case.as_ref()
+                .filter(|_| options.includes_case(name))
+                .map(|case| self._run_test_case(case, options.logging_enabled))
+        });
+
         TestCaseGroup {
-            example_day_1: self
-                .cases
-                .example_day_1
-                .as_ref()
-                .map(|case| self._run_test_case(case)),
-            day1: self
-                .cases
-                .day1
-                .as_ref()
-                .map(|case| self._run_test_case(case)),
-            example_day_2: self
-                .cases
-                .example_day_2
-                .as_ref()
-                .map(|case| self._run_test_case(case)),
-            day2: self
-                .cases
-                .day2
-                .as_ref()
-                .map(|case| self._run_test_case(case)),
+            example_day_1,
+            day1,
+            example_day_2,
+            day2,
         }
     }
 
-    fn _run_test_case(&self, test_case: &TestCase) -> TestCaseOutput {
-        let lines = match test_case.input {
-            Some(i) => i.lines().map(|s| s.to_string()).collect::<Vec<_>>(),
+    /// Solves one part for the given input, which is normalized and validated like the input of
+    /// a test case.
+    pub fn solve(&self, input: &str, part_number: u8) -> Result<Option<Answer>, String> {
+        self.solve_prepared(&self.prepare(input)?, part_number)
+    }
+
+    /// Normalizes and validates the input like the input of a test case, returning the normalized
+    /// input, which [`Assignment::parse`] takes.
+    pub fn prepare(&self, input: &str) -> Result<String, String> {
+        let input = self.normalization.apply(input);
+        if let Some(Err(e)) = self._validate.map(|validate| validate(&input)) {
+            return Err(format!("Invalid input on {}", e));
+        }
+
+        Ok(input)
+    }
+
+    /// Solves one part for an input that was already returned by [`Assignment::prepare`],
+    /// including parsing it.
+    pub fn solve_prepared(&self, input: &str, part_number: u8) -> Result<Option<Answer>, String> {
+        self.solve_parsed(&self.parse(input)?, part_number)
+    }
+
+    /// Parses an input that was already returned by [`Assignment::prepare`], so both parts can be
+    /// solved from it with [`Assignment::solve_parsed`].
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Solver<'a>, String> {
+        (self._parse)(input)
+    }
+
+    /// Solves one part from an input that was already returned by [`Assignment::parse`], without
+    /// parsing it again.
+    pub fn solve_parsed(&self, solver: &Solver, part_number: u8) -> Result<Option<Answer>, String> {
+        solver(AssignmentRuntimeContext {
+            part_number,
+            is_example: false,
+            logging_enabled: false,
+        })
+    }
+
+    /// Generates a random input of the given size, which is the same for the same seed. Returns
+    /// `None` if the assignment has no generator.
+    pub fn generate_input(&self, seed: u64, size: usize) -> Option<String> {
+        self._generate
+            .map(|generate| generate(&mut Random::new(seed), size))
+    }
+
+    /// The file that holds the real puzzle input, next to the assignment's source.
+    pub fn input_file_path(&self) -> String {
+        format!("src/assignments/assignment_{}.txt", self.day)
+    }
+
+    fn _run_test_case(&self, test_case: &TestCase, logging_enabled: bool) -> TestCaseOutput {
+        let raw_input = match test_case.input {
+            Some(i) => i.to_string(),
             None => {
-                let filename = format!("src/assignments/assignment_{}.txt", self.day);
-                let lines = match _read_lines(&filename) {
-                    Ok(lines) => lines,
+                let filename = self.input_file_path();
+                match std::fs::read_to_string(&filename) {
+                    Ok(contents) => contents,
                     Err(e) => panic!(
                         "
 Could not read file \"{}\": {}
@@ -259,43 +367,184 @@ This file should be located at \"{}\", but it could not be read.
 Create this file and try again.",
                         &filename, e, self.day, &filename
                     ),
-                };
-                lines.filter_map(Result::ok).collect::<Vec<_>>()
+                }
             }
         };
-        let lines = lines
-            .iter()
-            .skip_while(|a| a.is_empty())
-            .map(|a| a.to_string())
-            .collect::<Vec<_>>();
+        let input = self.normalization.apply(&raw_input);
 
         let expected = test_case.expected.clone();
 
-        let mut stopwatch = Stopwatch::start_new();
-        let actual = (self._f)(AssignmentRuntimeContext {
-            data: &lines,
-            part_number: test_case.part_number,
-            is_example: test_case.is_example,
-            logging_enabled: false,
+        if let Some(Err(e)) = self._validate.map(|validate| validate(&input)) {
+            return TestCaseOutput {
+                input,
+                expected,
+                actual: Err(format!("Invalid input on {}", e)),
+                runtime: Duration::ZERO,
+                allocations: None,
+                part_number: test_case.part_number,
+                is_example: test_case.is_example,
+            };
+        }
+
+        let ((actual, runtime), allocations) = allocation::measure(|| {
+            let mut stopwatch = Stopwatch::start_new();
+            let actual = (self._parse)(&input).and_then(|solve| {
+                solve(AssignmentRuntimeContext {
+                    part_number: test_case.part_number,
+                    is_example: test_case.is_example,
+                    logging_enabled,
+                })
+            });
+            let runtime = stopwatch.elapsed();
+            stopwatch.stop();
+            (actual, runtime)
         });
-        let runtime = stopwatch.elapsed();
-        stopwatch.stop();
 
         TestCaseOutput {
-            input: lines.join("\n"),
+            input,
             expected,
             actual,
             runtime,
+            allocations,
+            part_number: test_case.part_number,
+            is_example: test_case.is_example,
         }
     }
 }
 
-// The output is wrapped in a Result to allow matching on errors
-// Returns an Iterator to the Reader of the lines of the file.
-fn _read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{get_assignments, Answer, RunOptions};
+
+    /// Runs a single test case of an assignment. Fails if the case or its expected answer is
+    /// missing, so an incomplete assignment is never reported as passing. A case on the real input
+    /// is skipped with a message when its input file is missing.
+    fn _check_case(day: u32, case_name: &str) {
+        let assignment = get_assignments()
+            .into_iter()
+            .find(|assignment| assignment.day == day)
+            .unwrap_or_else(|| panic!("There is no assignment for day {}", day));
+
+        let Some((_, Some(case))) = assignment
+            .cases
+            .named()
+            .into_iter()
+            .find(|(name, _)| *name == case_name)
+        else {
+            panic!("Day {} has no {} case", day, case_name);
+        };
+        if case.input.is_none() && !Path::new(&assignment.input_file_path()).exists() {
+            println!(
+                "Skipping day {} {}: {} is not available",
+                day,
+                case_name,
+                assignment.input_file_path()
+            );
+            return;
+        }
+
+        let outputs = assignment.run(&RunOptions {
+            case: Some(case_name.to_string()),
+            logging_enabled: false,
+        });
+        let Some((_, Some(output))) = outputs
+            .named()
+            .into_iter()
+            .find(|(name, _)| *name == case_name)
+        else {
+            panic!("Day {} {} did not run", day, case_name);
+        };
+
+        match (&output.expected, &output.actual) {
+            (_, Err(e)) => panic!("Day {} {} failed: {}", day, case_name, e),
+            (Some(expected), Ok(actual)) => assert!(
+                actual.as_ref() == Some(expected),
+                "Day {} {}: expected {}, answered {}",
+                day,
+                case_name,
+                expected.to_string(),
+                actual
+                    .as_ref()
+                    .map_or("nothing".to_string(), Answer::to_string)
+            ),
+            (None, Ok(_)) => panic!("Day {} {} has no expected answer yet", day, case_name),
+        }
+    }
+
+    /// Generates a module per day, with a test per test case, so `cargo test day05` runs all
+    /// test cases of day 5 and `cargo test day05::example_2` runs a single one.
+    macro_rules! assignment_tests {
+        ($($module:ident: $day:literal),* $(,)?) => {
+            const TESTED_DAYS: &[u32] = &[$($day),*];
+
+            $(
+                mod $module {
+                    #[test]
+                    fn example_1() {
+                        super::_check_case($day, "Example 1");
+                    }
+
+                    #[test]
+                    fn part_1() {
+                        super::_check_case($day, "Day 1");
+                    }
+
+                    #[test]
+                    fn example_2() {
+                        super::_check_case($day, "Example 2");
+                    }
+
+                    #[test]
+                    fn part_2() {
+                        super::_check_case($day, "Day 2");
+                    }
+                }
+            )*
+        };
+    }
+
+    assignment_tests! {
+        day01: 1,
+        day02: 2,
+        day03: 3,
+        day04: 4,
+        day05: 5,
+        day06: 6,
+    }
+
+    #[test]
+    fn generates_valid_inputs() {
+        for assignment in get_assignments() {
+            for seed in 0..5 {
+                let Some(input) = assignment.generate_input(seed, 4) else {
+                    continue;
+                };
+
+                assert_eq!(assignment.generate_input(seed, 4), Some(input.clone()));
+                for part_number in [1, 2] {
+                    if let Err(e) = assignment.solve(&input, part_number) {
+                        panic!(
+                            "Day {} part {} failed on input generated with seed {}: {}\n{}",
+                            assignment.day, part_number, seed, e, input
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tests_every_assignment() {
+        let days = get_assignments()
+            .iter()
+            .map(|assignment| assignment.day)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            days, TESTED_DAYS,
+            "Add new assignments to the assignment_tests! invocation"
+        );
+    }
 }
@@ -0,0 +1,335 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::input::paragraphs;
+use super::InputError;
+
+/// A 1-based line and column in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub const START: Position = Position { line: 1, column: 1 };
+
+    /// Returns the position right after the given text, if the text starts at this position.
+    fn advance(self, text: &str) -> Position {
+        match text.rfind('\n') {
+            Some(index) => Position {
+                line: self.line + text.matches('\n').count(),
+                column: text[index + 1..].chars().count() + 1,
+            },
+            None => Position {
+                line: self.line,
+                column: self.column + text.chars().count(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.position.line, self.position.column, self.message
+        )
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(value: ParseError) -> Self {
+        value.to_string()
+    }
+}
+
+impl From<ParseError> for InputError {
+    fn from(value: ParseError) -> Self {
+        InputError {
+            line_number: value.position.line,
+            column: Some(value.position.column),
+            message: value.message,
+        }
+    }
+}
+
+/// A cursor over (a part of) the input that borrows from it and keeps track of where it is, so
+/// errors can point at the offending line and column.
+///
+/// Whitespace between tokens is skipped, except for line endings.
+#[derive(Debug, Clone, Copy)]
+pub struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    origin: Position,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Parser::at(text, Position::START)
+    }
+
+    fn at(text: &'a str, origin: Position) -> Self {
+        Parser {
+            text,
+            offset: 0,
+            origin,
+        }
+    }
+
+    /// Returns the text that has not been consumed yet.
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    pub fn position(&self) -> Position {
+        self.origin.advance(&self.text[..self.offset])
+    }
+
+    /// Creates an error at the current position.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            position: self.position(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.rest().trim().is_empty()
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    fn _take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.offset += length;
+        &rest[..length]
+    }
+
+    /// Consumes the given literal text.
+    pub fn literal(&mut self, literal: &str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.rest().starts_with(literal) {
+            true => {
+                self.offset += literal.len();
+                Ok(())
+            }
+            false => Err(self.error(format!("Expected \"{}\"", literal))),
+        }
+    }
+
+    /// Consumes a word, which ends at whitespace or punctuation other than `-` and `_`.
+    pub fn token(&mut self) -> Result<&'a str, ParseError> {
+        self.skip_whitespace();
+        let token = self._take_while(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        match token.is_empty() {
+            true => Err(self.error("Expected a word")),
+            false => Ok(token),
+        }
+    }
+
+    /// Consumes an optionally negative integer.
+    pub fn integer<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        self.skip_whitespace();
+        let start = *self;
+
+        let sign_length = usize::from(self.rest().starts_with('-'));
+        self.offset += sign_length;
+        let digits = self._take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            *self = start;
+            return Err(self.error("Expected an integer"));
+        }
+
+        let text = &start.rest()[..sign_length + digits.len()];
+        text.parse::<T>()
+            .map_err(|_| start.error(format!("Integer {} is out of range", text)))
+    }
+
+    /// Consumes all whitespace-separated integers up to the end of the line or the first
+    /// non-integer.
+    pub fn integers<T: FromStr>(&mut self) -> Result<Vec<T>, ParseError> {
+        let mut integers = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.rest().chars().next() {
+                Some(c) if c.is_ascii_digit() || c == '-' => integers.push(self.integer()?),
+                _ => return Ok(integers),
+            }
+        }
+    }
+
+    /// Succeeds only if nothing but whitespace is left.
+    pub fn end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.is_at_end() {
+            true => Ok(()),
+            false => {
+                let unexpected = self.rest().lines().next().unwrap_or_default();
+                Err(self.error(format!("Unexpected \"{}\"", unexpected)))
+            }
+        }
+    }
+
+    /// Splits the rest of the input at the first occurrence of the separator, such as the `:` of
+    /// a `key: value` header or the `|` between two groups.
+    pub fn split_once(&self, separator: char) -> Result<(Parser<'a>, Parser<'a>), ParseError> {
+        let rest = self.rest();
+        let Some(index) = rest.find(separator) else {
+            return Err(self.error(format!("Expected \"{}\"", separator)));
+        };
+
+        let position = self.position();
+        let (before, after) = (&rest[..index], &rest[index + separator.len_utf8()..]);
+        let after_position = position.advance(&rest[..index + separator.len_utf8()]);
+
+        Ok((
+            Parser::at(before, position),
+            Parser::at(after, after_position),
+        ))
+    }
+
+    /// Splits the rest of the input at every occurrence of the separator.
+    pub fn separated(&self, separator: &'a str) -> Separated<'a> {
+        Separated {
+            rest: Some(self.rest()),
+            position: self.position(),
+            separator,
+        }
+    }
+
+    /// Splits the rest of the input into lines.
+    pub fn lines(&self) -> Separated<'a> {
+        self.separated("\n")
+    }
+}
+
+/// An iterator over the parts of the input between separators. See [`Parser::separated`].
+pub struct Separated<'a> {
+    rest: Option<&'a str>,
+    position: Position,
+    separator: &'a str,
+}
+
+impl<'a> Iterator for Separated<'a> {
+    type Item = Parser<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        let position = self.position;
+
+        match rest.find(self.separator) {
+            Some(index) => {
+                let consumed = &rest[..index + self.separator.len()];
+                self.position = position.advance(consumed);
+                self.rest = Some(&rest[consumed.len()..]);
+                Some(Parser::at(&rest[..index], position))
+            }
+            None => {
+                self.rest = None;
+                Some(Parser::at(rest, position))
+            }
+        }
+    }
+}
+
+/// Splits the input into sections that are separated by one or more blank lines.
+pub fn sections(input: &str) -> impl Iterator<Item = Parser<'_>> {
+    let mut position = Position::START;
+    let mut consumed = 0;
+
+    paragraphs(input).map(move |paragraph| {
+        let offset = paragraph.as_ptr() as usize - input.as_ptr() as usize;
+        position = position.advance(&input[consumed..offset]);
+        consumed = offset;
+
+        Parser::at(paragraph, position)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _position(error: ParseError) -> (usize, usize) {
+        (error.position.line, error.position.column)
+    }
+
+    #[test]
+    fn reports_errors_at_the_line_and_column_of_each_line() {
+        let input = "Time: 7 15\nDistance: 9 x";
+        let mut lines = Parser::new(input).lines();
+
+        let mut time_line = lines.next().unwrap();
+        time_line.literal("Time:").unwrap();
+        assert_eq!(time_line.integers::<u32>().unwrap(), vec![7, 15]);
+        assert_eq!(_position(time_line.literal("|").unwrap_err()), (1, 11));
+
+        let mut distance_line = lines.next().unwrap();
+        assert_eq!(
+            _position(distance_line.literal("Time:").unwrap_err()),
+            (2, 1)
+        );
+        distance_line.literal("Distance:").unwrap();
+        assert_eq!(distance_line.integers::<u32>().unwrap(), vec![9]);
+        let error = distance_line.end().unwrap_err();
+        assert_eq!(_position(error.clone()), (2, 13));
+        assert_eq!(error.to_string(), "line 2, column 13: Unexpected \"x\"");
+    }
+
+    #[test]
+    fn reports_integer_errors_at_the_start_of_the_integer() {
+        let mut second_line = Parser::new("1\n  300 -").lines().nth(1).unwrap();
+
+        let mut too_small = second_line;
+        assert_eq!(_position(too_small.integer::<u8>().unwrap_err()), (2, 3));
+        assert_eq!(second_line.integer::<u16>().unwrap(), 300);
+        assert_eq!(_position(second_line.integer::<i8>().unwrap_err()), (2, 7));
+    }
+
+    #[test]
+    fn keeps_positions_across_splits() {
+        let input = "Card 1: 41 48 | 83 86\nCard 2: 13 xx | 61 30";
+        let card_2 = Parser::new(input).lines().nth(1).unwrap();
+
+        let (header, numbers) = card_2.split_once(':').unwrap();
+        assert_eq!(header.position(), Position { line: 2, column: 1 });
+        let (mut winning, _) = numbers.split_once('|').unwrap();
+        assert_eq!(winning.integer::<u32>().unwrap(), 13);
+        assert_eq!(_position(winning.end().unwrap_err()), (2, 12));
+
+        let mut groups = card_2.separated(" | ");
+        groups.next();
+        assert_eq!(
+            groups.next().unwrap().position(),
+            Position {
+                line: 2,
+                column: 17
+            }
+        );
+        assert_eq!(_position(card_2.split_once('#').unwrap_err()), (2, 1));
+    }
+
+    #[test]
+    fn starts_sections_after_blank_lines() {
+        let input = "seeds: 1\n\n\nmap:\n1 2 x";
+        let sections = sections(input).collect::<Vec<_>>();
+
+        assert_eq!(sections.len(), 2);
+        let mut mapping_line = sections[1].lines().nth(1).unwrap();
+        assert_eq!(mapping_line.integers::<u32>().unwrap(), vec![1, 2]);
+        assert_eq!(_position(mapping_line.end().unwrap_err()), (5, 5));
+    }
+}
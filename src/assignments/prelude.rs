@@ -2,6 +2,12 @@ pub use crate::assignments::Answer;
 pub use crate::assignments::Assignment;
 pub use crate::assignments::AssignmentOptions;
 pub use crate::assignments::AssignmentRuntimeContext;
+pub use crate::assignments::InputError;
+pub use crate::assignments::InputNormalization;
+pub use crate::assignments::ParseError;
+pub use crate::assignments::Parser;
+pub use crate::assignments::Random;
+pub use crate::assignments::Solver;
 pub use crate::assignments::TestCase;
 pub use crate::assignments::TestCaseGroup;
 pub use crate::assignments::TestCaseOutput;
@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+/// A small, seeded pseudo-random number generator (SplitMix64), so generated inputs can be
+/// reproduced from their seed without pulling in a dependency.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the given range, which must not be empty.
+    pub fn range(&mut self, range: Range<u64>) -> u64 {
+        assert!(
+            !range.is_empty(),
+            "Cannot pick from the empty range {:?}",
+            range
+        );
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+
+    /// Returns a random item of the slice, which must not be empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..items.len() as u64) as usize]
+    }
+
+    /// Shuffles the slice in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            let other_index = self.range(0..index as u64 + 1) as usize;
+            items.swap(index, other_index);
+        }
+    }
+}
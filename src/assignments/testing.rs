@@ -0,0 +1,132 @@
+//! Differential testing of assignments on generated inputs.
+//!
+//! Every assignment with an input generator can also be solved in the most straightforward way as
+//! a reference. [`check_against_reference`] then compares the real solver with that reference on
+//! many generated inputs, and shrinks any input on which they differ by generating smaller ones.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use super::random::Random;
+use super::{Answer, Assignment};
+
+/// The number of random inputs every property is checked on.
+pub const CASES: usize = 1_000;
+
+/// Checks that the property holds for `CASES` random seeds, each with a random size up to
+/// `max_size`. If it does not, the failing size is shrunk to the smallest one that still fails
+/// with the same seed, and the test panics with it.
+///
+/// The seeds are picked by the name of the property, or by the `PROPERTY_SEED` environment
+/// variable, so failures can be reproduced.
+pub fn check(name: &str, max_size: usize, property: impl Fn(u64, usize) -> Result<(), String>) {
+    let seed = std::env::var("PROPERTY_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(|| _hash(name));
+    let mut random = Random::new(seed);
+    let property = |seed: u64, size: usize| _catch_panics(|| property(seed, size));
+
+    for case in 0..CASES {
+        let case_seed = random.next_u64();
+        let size = random.range(1..max_size as u64 + 1) as usize;
+        if let Err(error) = property(case_seed, size) {
+            let (size, error) = _shrink(size, error, |size| property(case_seed, size));
+            panic!(
+                "Property \"{}\" failed on case {} of {} (seed {}). Smallest failing size with seed {} is {}:\n{}",
+                name, case + 1, CASES, seed, case_seed, size, error
+            );
+        }
+    }
+}
+
+/// Checks that both parts of the assignment answer the same as the reference for inputs
+/// generated with sizes up to `max_size`.
+pub fn check_against_reference(
+    assignment: &Assignment,
+    max_size: usize,
+    reference: impl Fn(&str, u8) -> Option<Answer>,
+) {
+    for part_number in [1, 2] {
+        let name = format!(
+            "day {} part {} matches reference",
+            assignment.day, part_number
+        );
+        check(&name, max_size, |seed, size| {
+            let input = assignment
+                .generate_input(seed, size)
+                .expect("The assignment has no input generator");
+            let expected = reference(&input, part_number);
+            match assignment.solve(&input, part_number) {
+                Ok(actual) if actual == expected => Ok(()),
+                actual => Err(format!(
+                    "Input:\n{}\nExpected {:?}, answered {:?}",
+                    input, expected, actual
+                )),
+            }
+        });
+    }
+}
+
+/// Returns the smallest size below the failing one that fails as well, with its error.
+fn _shrink(
+    size: usize,
+    error: String,
+    property: impl Fn(usize) -> Result<(), String>,
+) -> (usize, String) {
+    (1..size)
+        .find_map(|smaller_size| Some((smaller_size, property(smaller_size).err()?)))
+        .unwrap_or((size, error))
+}
+
+/// Turns a panic into an error, so panicking inputs are shrunk like any other failure.
+fn _catch_panics(check: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    catch_unwind(AssertUnwindSafe(check)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|m| m.to_string()))
+            .unwrap_or_default();
+        Err(format!("Panicked: {}", message))
+    })
+}
+
+/// A stable FNV-1a hash, so every property gets its own seed.
+fn _hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinks_to_smallest_failing_size() {
+        let property = |size: usize| match size < 5 {
+            true => Ok(()),
+            false => Err(format!("Size {} is too large", size)),
+        };
+
+        let (size, error) = _shrink(17, "Size 17 is too large".to_string(), property);
+
+        assert_eq!(size, 5);
+        assert_eq!(error, "Size 5 is too large");
+    }
+
+    #[test]
+    fn reports_panics_as_errors() {
+        let result = _catch_panics(|| panic!("Out of cubes"));
+
+        assert_eq!(result, Err("Panicked: Out of cubes".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Smallest failing size with seed")]
+    fn fails_with_counterexample() {
+        check("all sizes are small", 20, |_, size| match size < 18 {
+            true => Ok(()),
+            false => Err("Found a large size".to_string()),
+        });
+    }
+}
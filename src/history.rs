@@ -0,0 +1,146 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::allocation::AllocationStats;
+use crate::assignments::{TestCaseGroup, TestCaseOutput, TestCaseResult};
+
+/// The file every run is appended to, relative to the project root.
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch at which the run was recorded.
+    pub timestamp: u64,
+    /// The short hash of the checked out commit, suffixed with `-dirty` when the working tree
+    /// had uncommitted changes. `None` when git is unavailable.
+    pub commit: Option<String>,
+    pub day: u32,
+    pub part: u8,
+    pub is_example: bool,
+    pub result: TestCaseResult,
+    pub runtime_ns: u64,
+    /// Only recorded when allocations were counted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<AllocationStats>,
+}
+
+impl HistoryEntry {
+    /// The display name of the test case this entry belongs to, matching
+    /// [`TestCaseGroup::named`].
+    pub fn case_name(&self) -> String {
+        match self.is_example {
+            true => format!("Example {}", self.part),
+            false => format!("Day {}", self.part),
+        }
+    }
+}
+
+/// Appends one entry per executed test case of the given day to the history file.
+pub fn record(day: u32, outputs: &TestCaseGroup<Option<TestCaseOutput>>) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let commit = _current_commit();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(HISTORY_FILE)?;
+
+    for (_, output) in outputs.named() {
+        let Some(output) = output else {
+            continue;
+        };
+
+        let entry = HistoryEntry {
+            timestamp,
+            commit: commit.clone(),
+            day,
+            part: output.part_number,
+            is_example: output.is_example,
+            result: output.get_result(),
+            runtime_ns: output.runtime.as_nanos() as u64,
+            allocations: output.allocations,
+        };
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    }
+
+    Ok(())
+}
+
+/// Reads all recorded entries for the given day, oldest first.
+///
+/// A missing history file is treated as an empty history. Lines that cannot be parsed are
+/// skipped, so a partially written line never makes the whole history unreadable.
+pub fn load(day: u32) -> io::Result<Vec<HistoryEntry>> {
+    let file = match File::open(HISTORY_FILE) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(&line).ok())
+        .filter(|entry| entry.day == day)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    Ok(entries)
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds_of_day = timestamp % 86_400;
+
+    // Converts days since the epoch to a civil date.
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60
+    )
+}
+
+fn _current_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?.trim().to_string();
+
+    let is_dirty = Command::new("git")
+        .args(["status", "--porcelain"])
+        .output()
+        .map(|output| !output.stdout.is_empty())
+        .unwrap_or(false);
+
+    match is_dirty {
+        true => Some(format!("{hash}-dirty")),
+        false => Some(hash),
+    }
+}
@@ -0,0 +1,17 @@
+//! Solutions to [Advent of Code 2023](https://adventofcode.com/2023), together with the framework
+//! that runs, checks, times and reports them.
+//!
+//! Every day is an [`Assignment`], which [`get_assignments`] returns in order. Running an
+//! assignment yields a [`TestCaseOutput`] for each of its examples and real inputs.
+
+pub mod allocation;
+pub mod assignments;
+pub mod history;
+pub mod report;
+pub mod scaling;
+pub mod timing;
+
+pub use assignments::{
+    get_assignments, Answer, Assignment, AssignmentOptions, RunOptions, TestCase, TestCaseGroup,
+    TestCaseOutput, TestCaseResult,
+};
@@ -1,46 +1,140 @@
-mod assignments;
+use std::str::FromStr;
+use std::time::Duration;
 
-use assignments::{get_assignments, Assignment, TestCaseOutput};
+use aoc2023::report::{self, ReportRow};
+use aoc2023::timing::{self, RuntimeThresholds};
+use aoc2023::{
+    get_assignments, history, scaling, Assignment, RunOptions, TestCaseOutput, TestCaseResult,
+};
 use owo_colors::OwoColorize;
 
 fn throw_invalid_assignment_number_error() -> ! {
-    println!("Invalid assignment number.");
+    throw_usage_error("Invalid assignment number.");
+}
+
+fn throw_usage_error(message: &str) -> ! {
+    let assignment_numbers = get_assignments()
+        .iter()
+        .map(|a| a.day.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    println!("{}", message);
     println!(
-        "Usage: src/main.rs <assignment_number[{}]>",
-        get_assignments()
-            .iter()
-            .map(|a| a.day.to_string())
-            .collect::<Vec<_>>()
-            .join(", ")
+        "Usage: src/main.rs [assignment_number[{}]] [--case <case>] [--verbose]",
+        assignment_numbers
     );
+    println!("                   [--warn <duration>] [--slow <duration>]");
+    println!("       <case>: example1, day1, example2 or day2");
+    println!(
+        "       src/main.rs history <assignment_number[{}]>",
+        assignment_numbers
+    );
+    println!("       src/main.rs report [--readme] [--html <path>]");
+    println!(
+        "       src/main.rs generate [assignment_number[{}]] [--size <size>] [--seed <seed>]",
+        assignment_numbers
+    );
+    println!("                   [--output <directory>]");
+    println!(
+        "       src/main.rs scale <assignment_number[{}]> [--from <size>] [--steps <count>]",
+        assignment_numbers
+    );
+    println!("                   [--seed <seed>] [--repetitions <count>] [--csv <path>]");
     std::process::exit(1);
 }
 
 fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
 
-    if args.len() > 2 {
-        throw_invalid_assignment_number_error();
+    match args[..] {
+        ["history", n] => match n.parse::<u32>() {
+            Ok(n) => _print_history(n),
+            Err(_) => throw_invalid_assignment_number_error(),
+        },
+        ["report", ref options @ ..] => _run_report(options),
+        ["generate", ref options @ ..] => _generate_inputs(options),
+        ["scale", assignment_number, ref options @ ..] => match assignment_number.parse::<u32>() {
+            Ok(n) => _run_scaling(n, options),
+            Err(_) => throw_invalid_assignment_number_error(),
+        },
+        ref options => _run_command(options),
     }
+}
 
-    let should_run_single_assignment = args.len() > 1;
-    if !should_run_single_assignment {
-        _run_assignments(None)
-    } else {
-        match args[1].parse::<u32>() {
-            Ok(n) => _run_assignments(Some(n)),
-            Err(_) => throw_invalid_assignment_number_error(),
+fn _run_command(options: &[&str]) {
+    let mut assignment_number = None;
+    let mut thresholds = RuntimeThresholds::default();
+    let mut run_options = RunOptions::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--verbose" => run_options.logging_enabled = true,
+            "--case" => match options.next() {
+                Some(case) if _is_valid_case(case) => run_options.case = Some(case.to_string()),
+                Some(case) => throw_usage_error(&format!("Unknown case \"{}\".", case)),
+                None => throw_usage_error("Missing case for --case."),
+            },
+            "--warn" => thresholds.warn = _parse_duration_option(option, options.next()),
+            "--slow" => thresholds.slow = _parse_duration_option(option, options.next()),
+            n if assignment_number.is_none() && !n.starts_with("--") => match n.parse::<u32>() {
+                Ok(n) => assignment_number = Some(n),
+                Err(_) => throw_invalid_assignment_number_error(),
+            },
+            _ => throw_usage_error(&format!("Unknown option \"{}\".", option)),
         }
     }
+
+    _run_assignments(assignment_number, &run_options, &thresholds);
+}
+
+fn _is_valid_case(case: &str) -> bool {
+    let options = RunOptions {
+        case: Some(case.to_string()),
+        ..Default::default()
+    };
+    get_assignments().iter().any(|assignment| {
+        assignment
+            .cases
+            .named()
+            .iter()
+            .any(|(name, _)| options.includes_case(name))
+    })
+}
+
+fn _parse_duration_option(option: &str, value: Option<&&str>) -> Duration {
+    let Some(value) = value else {
+        throw_usage_error(&format!("Missing duration for {}.", option));
+    };
+    match timing::parse_duration(value) {
+        Ok(duration) => duration,
+        Err(e) => throw_usage_error(&format!("{}.", e)),
+    }
+}
+
+fn _parse_number_option<T: FromStr>(option: &str, value: Option<&&str>) -> T {
+    match value.map(|value| value.parse::<T>()) {
+        Some(Ok(number)) => number,
+        Some(Err(_)) => throw_usage_error(&format!("Invalid number for {}.", option)),
+        None => throw_usage_error(&format!("Missing number for {}.", option)),
+    }
 }
 
-fn _run_assignments(assignment_number: Option<u32>) {
+fn _run_assignments(
+    assignment_number: Option<u32>,
+    run_options: &RunOptions,
+    thresholds: &RuntimeThresholds,
+) {
     let assignments = get_assignments();
 
     if let Some(n) = assignment_number {
         let assignment = assignments.iter().find(|a| a.day == n);
         match assignment {
-            Some(a) => _run_single_assignment(a),
+            Some(a) => {
+                _run_single_assignment(a, run_options, thresholds);
+            }
             None => {
                 let error_text = format!(
                     "Assignment number {} does not exist. Please choose a valid assignment: {}",
@@ -56,21 +150,34 @@ fn _run_assignments(assignment_number: Option<u32>) {
             }
         }
     } else {
-        for assignment in assignments {
-            _run_single_assignment(&assignment);
-        }
+        let total_runtime = assignments
+            .iter()
+            .map(|assignment| _run_single_assignment(assignment, run_options, thresholds))
+            .sum::<Duration>();
+
+        println!();
+        println!(
+            "{} {}",
+            "Total runtime:".bold(),
+            timing::format_duration(total_runtime).bold()
+        );
     }
 }
 
-fn _run_single_assignment(assignment: &Assignment) {
+/// Runs and prints all test cases of the assignment, returning their combined runtime.
+fn _run_single_assignment(
+    assignment: &Assignment,
+    run_options: &RunOptions,
+    thresholds: &RuntimeThresholds,
+) -> Duration {
     println!(
         "{}",
         format!("Day {}: {}", assignment.day, assignment.description).bold()
     );
 
-    let outputs = assignment.run();
+    let outputs = assignment.run(run_options);
 
-    fn _output_result(name: &str, output: Option<&TestCaseOutput>) {
+    fn _output_result(name: &str, output: Option<&TestCaseOutput>, thresholds: &RuntimeThresholds) {
         const MAX_NAME_CHARS: u8 = 9;
         let pad_length = MAX_NAME_CHARS - name.len() as u8;
 
@@ -81,13 +188,7 @@ fn _run_single_assignment(assignment: &Assignment) {
             return;
         };
 
-        match output.get_result() {
-            assignments::TestCaseResult::NoAnswer => print!("{}", "❓ No answer.".yellow()),
-            assignments::TestCaseResult::Correct => print!("{}", "✅ Correct.".green()),
-            assignments::TestCaseResult::Incorrect => print!("{}", "❌ Incorrect.".bright_red()),
-            assignments::TestCaseResult::Error => print!("{}", "🚨 Error.".red().bold()),
-            assignments::TestCaseResult::Unknown => print!("{}", "🤷 Unknown.".bright_yellow()),
-        }
+        print!("{}", _format_result(output.get_result()));
 
         match &output.expected {
             Some(e) => print!(" Expected {}.", e.to_string()),
@@ -100,13 +201,346 @@ fn _run_single_assignment(assignment: &Assignment) {
             Err(e) => print!(" Error: {}.", e),
             _ => (),
         }
-        print!(" ({}ms)", output.runtime.as_millis());
+        match output.allocations {
+            Some(stats) => print!(" ({}, {})", thresholds.colorize(output.runtime), stats),
+            None => print!(" ({})", thresholds.colorize(output.runtime)),
+        }
 
         println!();
     }
 
-    _output_result("Example 1", outputs.example_day_1.as_ref());
-    _output_result("Day 1", outputs.day1.as_ref());
-    _output_result("Example 2", outputs.example_day_2.as_ref());
-    _output_result("Day 2", outputs.day2.as_ref());
+    for (name, output) in outputs.named() {
+        if run_options.includes_case(name) {
+            _output_result(name, output.as_ref(), thresholds);
+        }
+    }
+
+    let total_runtime = outputs
+        .named()
+        .into_iter()
+        .filter_map(|(_, output)| output.as_ref())
+        .map(|output| output.runtime)
+        .sum::<Duration>();
+    println!("  Total:      {}", timing::format_duration(total_runtime));
+
+    if let Err(e) = history::record(assignment.day, &outputs) {
+        println!(
+            "{}",
+            format!("Could not record run history: {}", e).yellow()
+        );
+    }
+
+    total_runtime
+}
+
+fn _format_result(result: TestCaseResult) -> String {
+    match result {
+        TestCaseResult::NoAnswer => "❓ No answer.".yellow().to_string(),
+        TestCaseResult::Correct => "✅ Correct.".green().to_string(),
+        TestCaseResult::Incorrect => "❌ Incorrect.".bright_red().to_string(),
+        TestCaseResult::Error => "🚨 Error.".red().bold().to_string(),
+        TestCaseResult::Unknown => "🤷 Unknown.".bright_yellow().to_string(),
+    }
+}
+
+fn _print_history(assignment_number: u32) {
+    let assignments = get_assignments();
+    let Some(assignment) = assignments.iter().find(|a| a.day == assignment_number) else {
+        throw_invalid_assignment_number_error();
+    };
+
+    println!(
+        "{}",
+        format!(
+            "Day {}: {} (history)",
+            assignment.day, assignment.description
+        )
+        .bold()
+    );
+
+    let entries = match history::load(assignment.day) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!(
+                "{}",
+                format!("Could not read run history: {}", e).bright_red()
+            );
+            std::process::exit(1);
+        }
+    };
+    if entries.is_empty() {
+        println!("  {}", "No runs recorded yet.".black());
+        return;
+    }
+
+    /// Runtime changes beyond this fraction of the previous run are highlighted.
+    const RUNTIME_CHANGE_THRESHOLD: f64 = 0.25;
+
+    for (name, _) in assignment.cases.named() {
+        let case_entries = entries
+            .iter()
+            .filter(|entry| entry.case_name() == name)
+            .collect::<Vec<_>>();
+        if case_entries.is_empty() {
+            continue;
+        }
+
+        println!("  - {}:", name);
+
+        let mut previous_runtime_ns: Option<u64> = None;
+        for entry in case_entries {
+            print!(
+                "      {}  {:<13} {}",
+                history::format_timestamp(entry.timestamp),
+                entry.commit.as_deref().unwrap_or("unknown"),
+                _format_result(entry.result)
+            );
+            let runtime = timing::format_duration(Duration::from_nanos(entry.runtime_ns));
+            match entry.allocations {
+                Some(stats) => print!(" ({}, {})", runtime, stats),
+                None => print!(" ({})", runtime),
+            }
+
+            if let Some(previous) = previous_runtime_ns.filter(|p| *p > 0) {
+                let change = (entry.runtime_ns as f64 - previous as f64) / previous as f64;
+                let change_text = format!(" {:+.0}%", change * 100.0);
+                if change > RUNTIME_CHANGE_THRESHOLD {
+                    print!("{}", change_text.bright_red());
+                } else if change < -RUNTIME_CHANGE_THRESHOLD {
+                    print!("{}", change_text.green());
+                } else {
+                    print!("{}", change_text.black());
+                }
+            }
+            previous_runtime_ns = Some(entry.runtime_ns);
+
+            println!();
+        }
+    }
+}
+
+fn _run_report(options: &[&str]) {
+    let mut update_readme = false;
+    let mut html_path = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--readme" => update_readme = true,
+            "--html" => match options.next() {
+                Some(path) => html_path = Some(*path),
+                None => throw_usage_error("Missing path for --html."),
+            },
+            _ => throw_usage_error(&format!("Unknown report option \"{}\".", option)),
+        }
+    }
+
+    let rows = get_assignments()
+        .iter()
+        .map(|assignment| {
+            let outputs = assignment.run(&RunOptions::default());
+            if let Err(e) = history::record(assignment.day, &outputs) {
+                eprintln!(
+                    "{}",
+                    format!("Could not record run history: {}", e).yellow()
+                );
+            }
+            ReportRow::new(assignment, &outputs)
+        })
+        .collect::<Vec<_>>();
+
+    let markdown = report::to_markdown(&rows);
+    println!("{}", markdown);
+
+    if update_readme {
+        const README_PATH: &str = "README.md";
+
+        let updated_readme = std::fs::read_to_string(README_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|readme| report::replace_section(&readme, &markdown))
+            .and_then(|readme| std::fs::write(README_PATH, readme).map_err(|e| e.to_string()));
+        match updated_readme {
+            Ok(()) => eprintln!("{}", format!("Updated {}.", README_PATH).green()),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Could not update {}: {}", README_PATH, e).bright_red()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = html_path {
+        match std::fs::write(path, report::to_html(&rows)) {
+            Ok(()) => eprintln!("{}", format!("Wrote {}.", path).green()),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Could not write {}: {}", path, e).bright_red()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn _generate_inputs(options: &[&str]) {
+    let mut assignment_number = None;
+    let mut size = 1000;
+    let mut seed = 1;
+    let mut output_directory = "generated";
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--size" => size = _parse_number_option(option, options.next()),
+            "--seed" => seed = _parse_number_option(option, options.next()),
+            "--output" => match options.next() {
+                Some(directory) => output_directory = directory,
+                None => throw_usage_error("Missing directory for --output."),
+            },
+            n if assignment_number.is_none() && !n.starts_with("--") => match n.parse::<u32>() {
+                Ok(n) => assignment_number = Some(n),
+                Err(_) => throw_invalid_assignment_number_error(),
+            },
+            _ => throw_usage_error(&format!("Unknown generate option \"{}\".", option)),
+        }
+    }
+
+    let assignments = get_assignments()
+        .into_iter()
+        .filter(|a| assignment_number.is_none_or(|n| a.day == n))
+        .collect::<Vec<_>>();
+    if assignments.is_empty() {
+        throw_invalid_assignment_number_error();
+    }
+
+    if let Err(e) = std::fs::create_dir_all(output_directory) {
+        eprintln!(
+            "{}",
+            format!("Could not create {}: {}", output_directory, e).bright_red()
+        );
+        std::process::exit(1);
+    }
+
+    for assignment in assignments {
+        let Some(input) = assignment.generate_input(seed, size) else {
+            println!(
+                "{}",
+                format!("Day {}: no input generator.", assignment.day).black()
+            );
+            continue;
+        };
+
+        let path = format!(
+            "{}/assignment_{}_size{}_seed{}.txt",
+            output_directory, assignment.day, size, seed
+        );
+        match std::fs::write(&path, &input) {
+            Ok(()) => println!(
+                "Day {}: wrote {} ({} lines).",
+                assignment.day,
+                path.green(),
+                input.lines().count()
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Could not write {}: {}", path, e).bright_red()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn _run_scaling(assignment_number: u32, options: &[&str]) {
+    let mut from: usize = 100;
+    let mut steps = 8;
+    let mut seed = 1;
+    let mut repetitions = 5;
+    let mut csv_path = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--from" => from = _parse_number_option(option, options.next()),
+            "--steps" => steps = _parse_number_option(option, options.next()),
+            "--seed" => seed = _parse_number_option(option, options.next()),
+            "--repetitions" => repetitions = _parse_number_option(option, options.next()),
+            "--csv" => match options.next() {
+                Some(path) => csv_path = Some(*path),
+                None => throw_usage_error("Missing path for --csv."),
+            },
+            _ => throw_usage_error(&format!("Unknown scale option \"{}\".", option)),
+        }
+    }
+
+    let assignment = match get_assignments()
+        .into_iter()
+        .find(|a| a.day == assignment_number)
+    {
+        Some(assignment) => assignment,
+        None => throw_invalid_assignment_number_error(),
+    };
+
+    // Doubling the size makes the growth of every complexity class equally visible.
+    let sizes = (0..steps)
+        .map(|step| {
+            let factor = u32::try_from(step)
+                .ok()
+                .and_then(|step| 1usize.checked_shl(step))?;
+            from.max(1).checked_mul(factor)
+        })
+        .collect::<Option<Vec<usize>>>()
+        .unwrap_or_else(|| {
+            throw_usage_error("The sizes for --from and --steps exceed the largest possible size.")
+        });
+    let Some(measurements) = scaling::measure(&assignment, &sizes, seed, repetitions) else {
+        eprintln!(
+            "{}",
+            format!("Day {} has no input generator.", assignment.day).bright_red()
+        );
+        std::process::exit(1);
+    };
+
+    println!(
+        "{}",
+        format!("Day {}: {}", assignment.day, assignment.description).bold()
+    );
+    for part_number in [1, 2] {
+        let points = measurements
+            .iter()
+            .filter_map(|measurement| {
+                let sample = measurement.parts[part_number as usize - 1].as_ref().ok()?;
+                Some((measurement.size, sample.runtime))
+            })
+            .collect::<Vec<_>>();
+        let fit = match scaling::fit(&points) {
+            Some(fit) => format!(
+                "{} (exponent {:.2})",
+                fit.complexity.label().yellow(),
+                fit.exponent
+            ),
+            None => "not enough measurements to fit".black().to_string(),
+        };
+
+        println!();
+        println!("Part {}: {}", part_number, fit);
+        println!("{}", scaling::to_chart(&measurements, part_number));
+    }
+
+    if let Some(path) = csv_path {
+        match std::fs::write(path, scaling::to_csv(&measurements)) {
+            Ok(()) => eprintln!("{}", format!("Wrote {}.", path).green()),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Could not write {}: {}", path, e).bright_red()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
 }
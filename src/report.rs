@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use crate::allocation::AllocationStats;
+use crate::assignments::{Assignment, TestCaseGroup, TestCaseOutput, TestCaseResult};
+use crate::timing::format_duration;
+
+/// Marks the start of the generated section in the README.
+pub const README_SECTION_START: &str = "<!-- report:start -->";
+/// Marks the end of the generated section in the README.
+pub const README_SECTION_END: &str = "<!-- report:end -->";
+
+/// A single row of the progress report, one per assignment.
+pub struct ReportRow {
+    pub day: u32,
+    pub title: &'static str,
+    pub part_1: Option<TestCaseResult>,
+    pub part_2: Option<TestCaseResult>,
+    pub runtime: Duration,
+    /// The heap usage of both parts together, if allocations are counted.
+    pub allocations: Option<AllocationStats>,
+}
+
+impl ReportRow {
+    /// Builds a row from the outputs of the real (non-example) test cases of an assignment.
+    pub fn new(assignment: &Assignment, outputs: &TestCaseGroup<Option<TestCaseOutput>>) -> Self {
+        let runtime = [&outputs.day1, &outputs.day2]
+            .into_iter()
+            .flatten()
+            .map(|output| output.runtime)
+            .sum();
+        let allocations = [&outputs.day1, &outputs.day2]
+            .into_iter()
+            .flatten()
+            .filter_map(|output| output.allocations)
+            .reduce(|total, stats| AllocationStats {
+                allocations: total.allocations + stats.allocations,
+                bytes: total.bytes + stats.bytes,
+                peak_bytes: total.peak_bytes.max(stats.peak_bytes),
+            });
+
+        ReportRow {
+            day: assignment.day,
+            title: assignment.description,
+            part_1: outputs.day1.as_ref().map(TestCaseOutput::get_result),
+            part_2: outputs.day2.as_ref().map(TestCaseOutput::get_result),
+            runtime,
+            allocations,
+        }
+    }
+}
+
+const HEADERS: [&str; 5] = ["Day", "Title", "Part 1", "Part 2", "Runtime"];
+const ALIGNMENTS: [&str; 5] = ["--:", ":--", ":-:", ":-:", "--:"];
+/// Only shown if any row has allocation stats, i.e. when the `count-allocations` feature is on.
+const ALLOCATIONS_HEADER: &str = "Allocations";
+
+fn _format_status(result: Option<TestCaseResult>) -> &'static str {
+    match result {
+        None => "➖",
+        Some(TestCaseResult::Correct) => "⭐",
+        Some(TestCaseResult::Incorrect) => "❌",
+        Some(TestCaseResult::Error) => "🚨",
+        Some(TestCaseResult::NoAnswer) => "❓",
+        Some(TestCaseResult::Unknown) => "🤷",
+    }
+}
+
+fn _has_allocations(rows: &[ReportRow]) -> bool {
+    rows.iter().any(|row| row.allocations.is_some())
+}
+
+fn _headers(rows: &[ReportRow]) -> Vec<&'static str> {
+    let mut headers = HEADERS.to_vec();
+    if _has_allocations(rows) {
+        headers.push(ALLOCATIONS_HEADER);
+    }
+    headers
+}
+
+fn _cells(row: &ReportRow, has_allocations: bool) -> Vec<String> {
+    let mut cells = vec![
+        row.day.to_string(),
+        row.title.to_string(),
+        _format_status(row.part_1).to_string(),
+        _format_status(row.part_2).to_string(),
+        format_duration(row.runtime),
+    ];
+    if has_allocations {
+        cells.push(
+            row.allocations
+                .map_or("➖".to_string(), |stats| stats.to_string()),
+        );
+    }
+    cells
+}
+
+/// Renders the rows as a Markdown table.
+pub fn to_markdown(rows: &[ReportRow]) -> String {
+    let has_allocations = _has_allocations(rows);
+    let mut alignments = ALIGNMENTS.to_vec();
+    if has_allocations {
+        alignments.push("--:");
+    }
+
+    let mut lines = vec![
+        format!("| {} |", _headers(rows).join(" | ")),
+        format!("| {} |", alignments.join(" | ")),
+    ];
+    lines.extend(
+        rows.iter()
+            .map(|row| format!("| {} |", _cells(row, has_allocations).join(" | "))),
+    );
+
+    lines.join("\n")
+}
+
+/// Renders the rows as a standalone HTML page.
+pub fn to_html(rows: &[ReportRow]) -> String {
+    let has_allocations = _has_allocations(rows);
+    let header_cells = _headers(rows)
+        .iter()
+        .map(|header| format!("<th>{}</th>", header))
+        .collect::<String>();
+    let body_rows = rows
+        .iter()
+        .map(|row| {
+            let cells = _cells(row, has_allocations)
+                .iter()
+                .map(|cell| format!("<td>{}</td>", _escape_html(cell)))
+                .collect::<String>();
+            format!("      <tr>{}</tr>", cells)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <title>Advent of Code 2023</title>
+    <style>
+      body {{ font-family: sans-serif; margin: 2em; }}
+      table {{ border-collapse: collapse; }}
+      th, td {{ border: 1px solid #ccc; padding: 0.3em 0.8em; }}
+      td:nth-child(1), td:nth-child(5), td:nth-child(6) {{ text-align: right; }}
+      td:nth-child(3), td:nth-child(4) {{ text-align: center; }}
+    </style>
+  </head>
+  <body>
+    <h1>Advent of Code 2023</h1>
+    <table>
+      <tr>{}</tr>
+{}
+    </table>
+  </body>
+</html>
+"#,
+        header_cells, body_rows
+    )
+}
+
+fn _escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Replaces everything between [`README_SECTION_START`] and [`README_SECTION_END`] in the given
+/// document with the given content, keeping the markers themselves.
+pub fn replace_section(document: &str, content: &str) -> Result<String, String> {
+    let start = document
+        .find(README_SECTION_START)
+        .ok_or(format!("Missing start marker \"{}\"", README_SECTION_START))?;
+    let end = document
+        .find(README_SECTION_END)
+        .ok_or(format!("Missing end marker \"{}\"", README_SECTION_END))?;
+    if end < start {
+        return Err(format!(
+            "End marker \"{}\" appears before start marker \"{}\"",
+            README_SECTION_END, README_SECTION_START
+        ));
+    }
+
+    let before = &document[..start + README_SECTION_START.len()];
+    let after = &document[end..];
+
+    Ok(format!("{}\n{}\n{}", before, content, after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _row(allocations: Option<AllocationStats>) -> ReportRow {
+        ReportRow {
+            day: 5,
+            title: "If You Give A Seed A Fertilizer",
+            part_1: Some(TestCaseResult::Correct),
+            part_2: None,
+            runtime: Duration::from_micros(250),
+            allocations,
+        }
+    }
+
+    #[test]
+    fn leaves_out_allocations_that_are_not_counted() {
+        let markdown = to_markdown(&[_row(None)]);
+
+        assert_eq!(
+            markdown,
+            "| Day | Title | Part 1 | Part 2 | Runtime |
+| --: | :-- | :-: | :-: | --: |
+| 5 | If You Give A Seed A Fertilizer | ⭐ | ➖ | 250µs |"
+        );
+    }
+
+    #[test]
+    fn shows_counted_allocations() {
+        let stats = AllocationStats {
+            allocations: 12,
+            bytes: 2048,
+            peak_bytes: 1024,
+        };
+
+        let markdown = to_markdown(&[_row(Some(stats)), _row(None)]);
+        let html = to_html(&[_row(Some(stats))]);
+
+        assert_eq!(
+            markdown,
+            "| Day | Title | Part 1 | Part 2 | Runtime | Allocations |
+| --: | :-- | :-: | :-: | --: | --: |
+| 5 | If You Give A Seed A Fertilizer | ⭐ | ➖ | 250µs | 12 allocations, 2.0KiB allocated, 1.0KiB peak |
+| 5 | If You Give A Seed A Fertilizer | ⭐ | ➖ | 250µs | ➖ |"
+        );
+        assert!(html.contains("<th>Allocations</th>"));
+        assert!(html.contains("<td>12 allocations, 2.0KiB allocated, 1.0KiB peak</td>"));
+    }
+}
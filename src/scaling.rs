@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use stopwatch::Stopwatch;
+
+use crate::allocation::{self, AllocationStats};
+use crate::assignments::Assignment;
+use crate::timing::format_duration;
+
+/// The width of the longest bar in the ASCII chart.
+const CHART_WIDTH: usize = 50;
+
+/// The fastest of all repetitions of one part.
+pub struct Sample {
+    pub runtime: Duration,
+    /// The heap usage of that repetition, if allocations are counted.
+    pub allocations: Option<AllocationStats>,
+}
+
+/// The samples of both parts on one generated input.
+pub struct Measurement {
+    pub size: usize,
+    pub parts: [Result<Sample, String>; 2],
+}
+
+/// Runs both parts of the assignment on generated inputs of each of the given sizes, timing how
+/// long parsing and solving take on an input that is normalized and validated beforehand. Returns `None` if the
+/// assignment has no generator.
+pub fn measure(
+    assignment: &Assignment,
+    sizes: &[usize],
+    seed: u64,
+    repetitions: usize,
+) -> Option<Vec<Measurement>> {
+    sizes
+        .iter()
+        .map(|&size| {
+            let input = assignment.generate_input(seed, size)?;
+            let prepared = assignment.prepare(&input);
+            let parts = [1, 2].map(|part_number| {
+                let prepared = prepared.as_ref().map_err(String::clone)?;
+                (0..repetitions.max(1))
+                    .map(|_| {
+                        let (runtime, allocations) = allocation::measure(|| {
+                            let stopwatch = Stopwatch::start_new();
+                            assignment
+                                .solve_prepared(prepared, part_number)
+                                .map(|_| stopwatch.elapsed())
+                        });
+                        runtime.map(|runtime| Sample {
+                            runtime,
+                            allocations,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|samples| {
+                        samples
+                            .into_iter()
+                            .min_by_key(|sample| sample.runtime)
+                            .unwrap()
+                    })
+            });
+
+            Some(Measurement { size, parts })
+        })
+        .collect()
+}
+
+/// The complexity classes a series of measurements is fitted against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Complexity {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+    Cubic,
+}
+
+impl Complexity {
+    const ALL: [Complexity; 6] = [
+        Complexity::Constant,
+        Complexity::Logarithmic,
+        Complexity::Linear,
+        Complexity::Linearithmic,
+        Complexity::Quadratic,
+        Complexity::Cubic,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Complexity::Constant => "O(1)",
+            Complexity::Logarithmic => "O(log n)",
+            Complexity::Linear => "O(n)",
+            Complexity::Linearithmic => "O(n log n)",
+            Complexity::Quadratic => "O(n²)",
+            Complexity::Cubic => "O(n³)",
+        }
+    }
+
+    /// The natural logarithm of the growth function at `n`, up to a constant.
+    fn _log_model(&self, n: f64) -> f64 {
+        match self {
+            Complexity::Constant => 0.0,
+            Complexity::Logarithmic => n.ln().ln(),
+            Complexity::Linear => n.ln(),
+            Complexity::Linearithmic => n.ln() + n.ln().ln(),
+            Complexity::Quadratic => 2.0 * n.ln(),
+            Complexity::Cubic => 3.0 * n.ln(),
+        }
+    }
+}
+
+/// The empirical complexity of one part.
+pub struct Fit {
+    /// The complexity class that explains the runtimes best.
+    pub complexity: Complexity,
+    /// The slope of the runtimes on a log-log scale, i.e. the `k` in `O(n^k)`.
+    pub exponent: f64,
+}
+
+/// Fits the runtimes at the given sizes to a complexity class, by the least squares of the
+/// logarithms of the runtimes. Returns `None` with fewer than three distinct sizes, as two points
+/// fit anything.
+pub fn fit(points: &[(usize, Duration)]) -> Option<Fit> {
+    let points = points
+        .iter()
+        .filter(|(size, _)| *size >= 2)
+        .map(|(size, runtime)| {
+            let nanos = runtime.as_nanos().max(1) as f64;
+            (*size as f64, nanos.ln())
+        })
+        .collect::<Vec<_>>();
+
+    let mut sizes = points.iter().map(|(n, _)| *n as u64).collect::<Vec<_>>();
+    sizes.dedup();
+    if sizes.len() < 3 {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mean_log_n = points.iter().map(|(n, _)| n.ln()).sum::<f64>() / count;
+    let mean_log_t = points.iter().map(|(_, t)| t).sum::<f64>() / count;
+    let covariance = points
+        .iter()
+        .map(|(n, t)| (n.ln() - mean_log_n) * (t - mean_log_t))
+        .sum::<f64>();
+    let variance = points
+        .iter()
+        .map(|(n, _)| (n.ln() - mean_log_n).powi(2))
+        .sum::<f64>();
+
+    // Each model only differs from the runtimes by a constant factor, which becomes an offset on
+    // a log scale. The best offset is the mean difference, so the error is its variance.
+    let error = |complexity: &Complexity| {
+        let differences = points
+            .iter()
+            .map(|(n, t)| t - complexity._log_model(*n))
+            .collect::<Vec<_>>();
+        let mean = differences.iter().sum::<f64>() / count;
+        differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>()
+    };
+    let complexity = Complexity::ALL
+        .into_iter()
+        .min_by(|a, b| error(a).total_cmp(&error(b)))
+        .unwrap();
+
+    Some(Fit {
+        complexity,
+        exponent: covariance / variance,
+    })
+}
+
+/// Renders the measurements as CSV, with the runtimes in nanoseconds and the heap usage in
+/// allocations and bytes. Failed runs and allocations that are not counted are left empty.
+pub fn to_csv(measurements: &[Measurement]) -> String {
+    let part_columns = [1, 2].map(|part_number| {
+        format!(
+            "part_{0}_ns,part_{0}_allocations,part_{0}_bytes,part_{0}_peak_bytes",
+            part_number
+        )
+    });
+    let mut lines = vec![format!("size,{}", part_columns.join(","))];
+    lines.extend(measurements.iter().map(|measurement| {
+        let [part_1, part_2] = measurement.parts.each_ref().map(|sample| {
+            let sample = sample.as_ref().ok();
+            let stats = sample.and_then(|sample| sample.allocations);
+            [
+                sample.map(|sample| sample.runtime.as_nanos() as u64),
+                stats.map(|stats| stats.allocations),
+                stats.map(|stats| stats.bytes),
+                stats.map(|stats| stats.peak_bytes),
+            ]
+            .map(|value| value.map(|value| value.to_string()).unwrap_or_default())
+            .join(",")
+        });
+        format!("{},{},{}", measurement.size, part_1, part_2)
+    }));
+
+    lines.join("\n") + "\n"
+}
+
+/// Renders the runtimes of one part as a horizontal bar chart, one bar per size.
+pub fn to_chart(measurements: &[Measurement], part_number: u8) -> String {
+    let index = (part_number - 1) as usize;
+    let longest = measurements
+        .iter()
+        .filter_map(|measurement| measurement.parts[index].as_ref().ok())
+        .map(|sample| sample.runtime)
+        .max()
+        .unwrap_or_default();
+    let size_width = measurements
+        .iter()
+        .map(|measurement| measurement.size.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    measurements
+        .iter()
+        .map(|measurement| {
+            let bar = match &measurement.parts[index] {
+                Ok(Sample {
+                    runtime,
+                    allocations,
+                }) => {
+                    let length = match longest.is_zero() {
+                        true => 0,
+                        false => (runtime.as_secs_f64() / longest.as_secs_f64()
+                            * CHART_WIDTH as f64)
+                            .round() as usize,
+                    };
+                    let runtime = format_duration(*runtime);
+                    match allocations {
+                        Some(stats) => format!(
+                            "{:<width$} {} ({})",
+                            "█".repeat(length),
+                            runtime,
+                            stats,
+                            width = CHART_WIDTH
+                        ),
+                        None => format!(
+                            "{:<width$} {}",
+                            "█".repeat(length),
+                            runtime,
+                            width = CHART_WIDTH
+                        ),
+                    }
+                }
+                Err(e) => format!("error: {}", _shorten(e)),
+            };
+            format!("{:>width$} │{}", measurement.size, bar, width = size_width)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cuts the message off after the width of the chart, as errors can quote huge numbers.
+fn _shorten(message: &str) -> String {
+    match message.char_indices().nth(CHART_WIDTH) {
+        Some((index, _)) => format!("{}…", &message[..index]),
+        None => message.to_string(),
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use owo_colors::OwoColorize;
+
+/// Formats a duration in the largest unit (ns, µs, ms or s) in which it is at least 1, rounded
+/// to three significant digits.
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos() as f64;
+    if nanos < 1e3 {
+        return format!("{}ns", nanos);
+    }
+
+    // Rounds before picking the unit, so 999.6µs becomes 1.00ms instead of 1000µs.
+    let magnitude = nanos.log10().floor() as i32;
+    let precision = 10f64.powi(magnitude - 2);
+    let nanos = (nanos / precision).round() * precision;
+
+    let (value, unit) = if nanos < 1e6 {
+        (nanos / 1e3, "µs")
+    } else if nanos < 1e9 {
+        (nanos / 1e6, "ms")
+    } else {
+        (nanos / 1e9, "s")
+    };
+
+    let decimals = if value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    };
+
+    format!("{:.*}{}", decimals, value, unit)
+}
+
+/// Parses a duration such as `250ms`, `1.5s`, `40µs`/`40us` or `900ns`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split_index = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .ok_or(format!("Missing unit in duration \"{}\"", text))?;
+    let (value, unit) = text.split_at(split_index);
+
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid number in duration \"{}\"", text))?;
+    let nanos_per_unit = match unit {
+        "ns" => 1e0,
+        "µs" | "us" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        _ => {
+            return Err(format!(
+                "Unknown unit \"{}\" in duration \"{}\"",
+                unit, text
+            ))
+        }
+    };
+
+    Ok(Duration::from_nanos((value * nanos_per_unit).round() as u64))
+}
+
+/// The limits at which runtimes are considered slow (yellow) or too slow (red).
+pub struct RuntimeThresholds {
+    pub warn: Duration,
+    pub slow: Duration,
+}
+
+impl Default for RuntimeThresholds {
+    fn default() -> Self {
+        RuntimeThresholds {
+            warn: Duration::from_millis(100),
+            slow: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RuntimeThresholds {
+    /// Formats the duration using [`format_duration`], colored by these thresholds.
+    pub fn colorize(&self, duration: Duration) -> String {
+        let text = format_duration(duration);
+
+        if duration >= self.slow {
+            text.bright_red().to_string()
+        } else if duration >= self.warn {
+            text.yellow().to_string()
+        } else {
+            text.green().to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(999, "999ns")]
+    #[test_case(1_000, "1.00µs")]
+    #[test_case(12_345, "12.3µs")]
+    #[test_case(999_499, "999µs")]
+    #[test_case(999_600, "1.00ms")]
+    #[test_case(9_996_000, "10.0ms")]
+    #[test_case(99_960_000, "100ms")]
+    #[test_case(999_600_000, "1.00s")]
+    #[test_case(1_234_000_000_000, "1230s")]
+    fn formats_durations_with_three_significant_digits(nanos: u64, expected: &str) {
+        assert_eq!(format_duration(Duration::from_nanos(nanos)), expected);
+    }
+}